@@ -1,51 +1,123 @@
 extern crate aoc2017;
 
 use aoc2017::input;
+use std::cmp;
+use std::collections::HashMap;
 
 fn main() {
     if let Some(data) = input::load(8) {
-        let instructions = parse_instructions(data.clone().into_bytes()).unwrap();
+        let instructions = parse_instructions(&data).unwrap();
 
-        let mut result = p1(&instructions).unwrap();
-        println!("{}", result);
+        let res1 = p1(&instructions);
+        println!("{}", res1);
 
-        result = p2(&instructions).unwrap();
+        let res2 = p2(&instructions);
         println!("{}", res2);
     }
 }
 
 #[derive(Debug, Copy, Clone, PartialEq)]
 enum Error {
-    InvalidCondition,
+    BadInstruction,
+    UnknownOperation,
+    UnknownCondition,
 }
 
-fn parse_input(raw: Vec<u8>) -> Result<Vec<Instruction>, Error> {
-    let mut instructions: Vec<u8> = Vec::new();
-    let mut line = String::new();
-    loop {
-    }
-}
-
-fn parse_instruction(input: &str) -> Result<Instruction, std::num::ParseIntError> {
+// Parses one instruction per line, such as "b inc 5 if a > 1".
+fn parse_instructions(input: &str) -> Result<Vec<Instruction>, Error> {
     input
-        .split_whitespace()
-        .map(|token| token.parse())
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(parse_instruction)
         .collect()
 }
 
+fn parse_instruction(input: &str) -> Result<Instruction, Error> {
+    let tokens: Vec<&str> = input.split_whitespace().collect();
+    if tokens.len() != 7 || tokens[3] != "if" {
+        return Err(Error::BadInstruction);
+    }
+
+    let value: i32 = tokens[2].parse().map_err(|_| Error::BadInstruction)?;
+    let value = match tokens[1] {
+        "inc" => value,
+        "dec" => -value,
+        _ => return Err(Error::UnknownOperation),
+    };
+    let condition: fn(&i32, &i32) -> bool = match tokens[5] {
+        ">" => PartialOrd::gt,
+        "<" => PartialOrd::lt,
+        "==" => PartialEq::eq,
+        "!=" => PartialEq::ne,
+        ">=" => PartialOrd::ge,
+        "<=" => PartialOrd::le,
+        _ => return Err(Error::UnknownCondition),
+    };
+    let test_value: i32 = tokens[6].parse().map_err(|_| Error::BadInstruction)?;
+
+    Ok(Instruction {
+        register: tokens[0].to_string(),
+        value,
+        test_register: tokens[4].to_string(),
+        condition,
+        test_value,
+    })
+}
+
 struct Instruction {
-    register: &str
-    operation: &str
-    value: i32
-    test_register: &str
-    condition: &str
-    test_value: i32
+    register: String,
+    // How much the register changes by; "dec" instructions are negated.
+    value: i32,
+    test_register: String,
+    condition: fn(&i32, &i32) -> bool,
+    test_value: i32,
+}
+
+// Runs the instructions, and returns the registers they leave behind, along
+// with the largest value any register held along the way.
+// Registers start at 0, so neither is ever less than 0.
+fn run(instructions: &[Instruction]) -> (HashMap<&str, i32>, i32) {
+    let mut registers: HashMap<&str, i32> = HashMap::new();
+    let mut max = 0;
+    for ins in instructions {
+        let test = *registers.get(ins.test_register.as_str()).unwrap_or(&0);
+        if (ins.condition)(&test, &ins.test_value) {
+            let v = registers.entry(ins.register.as_str()).or_insert(0);
+            *v += ins.value;
+            max = cmp::max(max, *v);
+        }
+    }
+    (registers, max)
 }
 
-fn p1(instructions: Vec<Instruction>) -> i32 {
-    0
+// Find the largest value in any register, once every instruction has run.
+fn p1(instructions: &[Instruction]) -> i32 {
+    let (registers, _) = run(instructions);
+    registers.values().fold(0, |max, &v| cmp::max(max, v))
 }
 
-fn p2(instructions: Vec<Instruction>) -> i32 {
-    0
+// Find the largest value held in any register while the instructions run.
+fn p2(instructions: &[Instruction]) -> i32 {
+    let (_, max) = run(instructions);
+    max
+}
+
+#[test]
+fn test_example() {
+    let input = "b inc 5 if a > 1
+a inc 1 if b < 5
+c dec -10 if a >= 1
+c inc -20 if c == 10";
+    let instructions = parse_instructions(input).unwrap();
+    assert_eq!(p1(&instructions), 1);
+    assert_eq!(p2(&instructions), 10);
+
+    assert_eq!(
+        parse_instructions("a mul 2 if b > 1").err(),
+        Some(Error::UnknownOperation)
+    );
+    assert_eq!(
+        parse_instructions("a inc 2 if b <> 1").err(),
+        Some(Error::UnknownCondition)
+    );
 }
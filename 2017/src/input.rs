@@ -0,0 +1,12 @@
+//! Loading puzzle input for a given day.
+
+use aoc_common::Input;
+
+/// Loads the puzzle input for `day` from the file `input.<day>` in the
+/// current directory, with any surrounding whitespace removed.
+///
+/// Returns `None` if the file cannot be read.
+pub fn load(day: u8) -> Option<String> {
+    let input = Input::from_file(format!("input.{}", day)).ok()?;
+    Some(input.to_string())
+}
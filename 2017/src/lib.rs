@@ -1,5 +1,9 @@
+extern crate aoc_common;
+
 pub mod input;
 
+pub use aoc_common::{include_input, util, Input, InputError, Section};
+
 #[cfg(test)]
 mod tests {
     #[test]
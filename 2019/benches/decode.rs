@@ -0,0 +1,97 @@
+// Compare executing intcode programs with and without predecoded
+// instructions.
+//
+// The benchmarks run the day 9 BOOST program, and probe the day 19 tractor
+// beam, using the puzzle inputs in input/09 and input/19.
+// Without them, they run the day 9 example that outputs a copy of itself, and
+// a small stand-in for the tractor beam program, instead.
+use aoc::{
+    intcode::{assemble, Program},
+    Input,
+};
+use criterion::{criterion_group, criterion_main, Criterion};
+
+// Day 9's example program, which outputs a copy of itself.
+const QUINE: &str = "109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99";
+
+// Outputs 1 if the point (x, y) read from input is in a beam that widens
+// away from the origin, where 13x <= 10y <= 17x, and 0 otherwise.
+const BEAM: &str = "
+            in -> [x]
+            in -> [y]
+            mul [x], #13 -> [lo]
+            mul [x], #17 -> [hi]
+            mul [y], #10 -> [y]
+            lt [y], [lo] -> [t]
+            jt [t], #out
+            lt [hi], [y] -> [t]
+            jt [t], #out
+            add #1, #0 -> [r]
+    out:    out [r]
+            hlt
+    x:      data 0
+    y:      data 0
+    lo:     data 0
+    hi:     data 0
+    t:      data 0
+    r:      data 0
+";
+
+// Loads the program in `path`, if there is one.
+fn load(path: &str) -> Option<Program> {
+    let input = Input::from_file(path).ok()?;
+    Some(Program::from(input.as_str()))
+}
+
+fn boost(c: &mut Criterion) {
+    let (program, input) = match load("input/09") {
+        Some(program) => (program, vec![2]),
+        None => (Program::from(QUINE), vec![]),
+    };
+
+    let mut group = c.benchmark_group("boost");
+    for &cached in &[true, false] {
+        let mut program = program.clone();
+        program.set_decode_cache(cached);
+        let id = if cached { "cached" } else { "uncached" };
+        group.bench_function(id, |b| {
+            b.iter(|| {
+                program.reset_to_initial();
+                for &v in &input {
+                    program.input(v);
+                }
+                program.execute().unwrap()
+            })
+        });
+    }
+    group.finish();
+}
+
+fn beam(c: &mut Criterion) {
+    let program = load("input/19").unwrap_or_else(|| Program::from(assemble(BEAM).unwrap()));
+
+    // Probe a 50x50 area, like day 19 part 1 does, with a fresh copy of the
+    // program for every point.
+    let mut group = c.benchmark_group("beam");
+    for &cached in &[true, false] {
+        let mut program = program.clone();
+        program.set_decode_cache(cached);
+        let id = if cached { "cached" } else { "uncached" };
+        group.bench_function(id, |b| {
+            b.iter(|| {
+                let mut points = 0;
+                for y in 0..50 {
+                    for x in 0..50 {
+                        let mut probe = program.clone();
+                        points += probe.input(x).input(y).execute().unwrap()[0];
+                    }
+                }
+                points
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, boost, beam);
+criterion_main!(benches);
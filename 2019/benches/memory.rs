@@ -0,0 +1,62 @@
+// Compare forking intcode programs with flat and paged memory.
+use aoc::intcode::{assemble, Backend, Program};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+
+// Sums the numbers from the input down to 1, in a program padded out to
+// several KB of memory, most of which is never touched.
+const SUM: &str = "
+            in -> [n]
+    loop:   add [acc], [n] -> [acc]
+            add [n], #-1 -> [n]
+            jt [n], #loop
+            out [acc]
+            hlt
+    n:      data 0
+    acc:    data 0
+";
+
+fn program(size: usize, backend: Backend) -> Program {
+    let mut mem = assemble(SUM).unwrap();
+    mem.resize(size, 0);
+    let mut program = Program::from(mem);
+    program.set_backend(backend);
+    program.execute().unwrap();
+    program
+}
+
+fn fork(c: &mut Criterion) {
+    let mut group = c.benchmark_group("fork");
+    for &size in &[1 << 12, 1 << 16] {
+        for &backend in &[Backend::Flat, Backend::Paged] {
+            let mut program = program(size, backend);
+            let id = BenchmarkId::new(format!("{:?}", backend), size);
+            group.bench_function(id, |b| {
+                b.iter(|| {
+                    let mut fork = match backend {
+                        Backend::Flat => program.clone(),
+                        Backend::Paged => program.fork(),
+                    };
+                    fork.input(10).execute().unwrap()
+                })
+            });
+        }
+    }
+    group.finish();
+}
+
+fn execute(c: &mut Criterion) {
+    let mut group = c.benchmark_group("execute");
+    for &backend in &[Backend::Flat, Backend::Paged] {
+        let program = program(1 << 12, backend);
+        group.bench_function(format!("{:?}", backend), |b| {
+            b.iter(|| {
+                let mut program = program.clone();
+                program.input(10_000).execute().unwrap()
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, fork, execute);
+criterion_main!(benches);
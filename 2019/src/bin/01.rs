@@ -1,19 +1,11 @@
-use std::fs::File;
-use std::io::{BufRead, BufReader, Result};
+use aoc::Input;
+use std::io::Result;
 
 fn main() -> Result<()> {
-    let in_file: String = match std::env::args().nth(1) {
-        Some(v) => v,
-        None => {
-            eprintln!("Specify a path to the input file");
-            std::process::exit(1);
-        }
-    };
-    let file = File::open(in_file)?;
+    let input = Input::from_args()?;
     let mut fuel_per_component: i32 = 0;
     let mut total_fuel_required: i32 = 0;
-    for line in BufReader::new(file).lines() {
-        let mass: i32 = line.unwrap().parse().unwrap();
+    for mass in input.parse_lines::<i32>()? {
         fuel_per_component += calc_fuel(mass);
         total_fuel_required += calc_fuel_weight(mass);
     }
@@ -4,43 +4,27 @@ use std::io::{Error, ErrorKind, Result};
 use aoc::{Direction, Directions, Input, LineSegment, Point};
 
 fn main() -> Result<()> {
-    let in_file: String = match std::env::args().nth(1) {
-        Some(v) => v,
-        None => {
-            return Err(Error::new(
-                ErrorKind::InvalidInput,
-                "no input file specified",
-            ));
-        }
-    };
-    let input = Input::from_file(in_file)?;
+    let input = Input::from_args()?;
 
     // Collect the directions into line segments.
     let mut paths: Vec<Vec<LineSegment>> = vec![];
     let mut directions: Vec<Vec<Direction>> = vec![];
-    match input.split_lines(",") {
-        Some(lines) => {
-            for line in lines {
-                let mut dirs: Vec<Direction> = vec![];
-                for l in line.into_iter() {
-                    dirs.push(Direction::new(&l));
-                }
-
-                let mut path: Vec<LineSegment> = vec![];
-                let mut start = Point::new(0, 0);
-                for d in dirs.iter() {
-                    let end = start.travel(&d);
-                    path.push(LineSegment::new(start, end));
-                    start = end;
-                }
-
-                directions.push(dirs);
-                paths.push(path);
-            }
+    for line in input.split_lines(",") {
+        let mut dirs: Vec<Direction> = vec![];
+        for l in line.into_iter() {
+            dirs.push(Direction::new(&l));
         }
-        None => {
-            return Err(Error::new(ErrorKind::Other, "No data returned from input"));
+
+        let mut path: Vec<LineSegment> = vec![];
+        let mut start = Point::new(0, 0);
+        for d in dirs.iter() {
+            let end = start.travel(&d);
+            path.push(LineSegment::new(start, end));
+            start = end;
         }
+
+        directions.push(dirs);
+        paths.push(path);
     }
 
     let distance = part1(&paths)?;
@@ -58,7 +42,6 @@ fn test_part1() {
     let input = Input::from(raw_input);
     let wires: Vec<Vec<LineSegment>> = input
         .split_lines(",")
-        .unwrap()
         .iter()
         .map(|v| {
             let dirs = Directions::new(v.iter().map(|d| Direction::new(d)).collect());
@@ -72,7 +55,6 @@ fn test_part1() {
     let input = Input::from(raw_input);
     let wires: Vec<Vec<LineSegment>> = input
         .split_lines(",")
-        .unwrap()
         .iter()
         .map(|v| {
             let dirs = Directions::new(v.iter().map(|d| Direction::new(d)).collect());
@@ -87,7 +69,6 @@ fn test_part1() {
     let input = Input::from(raw_input);
     let wires: Vec<Vec<LineSegment>> = input
         .split_lines(",")
-        .unwrap()
         .iter()
         .map(|v| {
             let dirs = Directions::new(v.iter().map(|d| Direction::new(d)).collect());
@@ -154,7 +135,6 @@ fn test_part2() {
     let input = Input::new_from_str("R8,U5,L5,D3\nU7,R6,D4,L4").unwrap();
     let directions: Vec<Vec<Direction>> = input
         .split_lines(",")
-        .unwrap()
         .iter()
         .map(|v| v.into_iter().map(|u| Direction::new(u)).collect())
         .collect();
@@ -165,7 +145,6 @@ fn test_part2() {
     let input = Input::new_from_str(raw_input).unwrap();
     let directions: Vec<Vec<Direction>> = input
         .split_lines(",")
-        .unwrap()
         .iter()
         .map(|v| v.into_iter().map(|u| Direction::new(u)).collect())
         .collect();
@@ -177,7 +156,6 @@ fn test_part2() {
     let input = Input::new_from_str(raw_input).unwrap();
     let directions: Vec<Vec<Direction>> = input
         .split_lines(",")
-        .unwrap()
         .iter()
         .map(|v| v.into_iter().map(|u| Direction::new(u)).collect())
         .collect();
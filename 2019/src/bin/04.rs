@@ -26,17 +26,8 @@ use aoc::Input;
 use std::io::{Error, ErrorKind, Result};
 
 fn main() -> Result<()> {
-    let in_file: String = match std::env::args().nth(1) {
-        Some(v) => v,
-        None => {
-            return Err(Error::new(
-                ErrorKind::InvalidInput,
-                "no input file specified",
-            ));
-        }
-    };
-    let input = Input::from_file(in_file)?;
-    let input_string = input.to_string().unwrap();
+    let input = Input::from_args()?;
+    let input_string = input.to_string();
     let sep = match input_string.find("-") {
         Some(n) => n,
         None => {
@@ -3,27 +3,20 @@ use aoc::{
     Input,
     intcode::Program,
 };
-use std::io::{Error, ErrorKind, Result};
+use std::io::Result;
 
 fn main() -> Result<()> {
-    let input_file: String = match std::env::args().nth(1) {
-        Some(v) => v,
-        None => {
-            return Err(Error::new(ErrorKind::InvalidInput, "no input file specified"));
-        },
-    };
-    let input = Input::from_file(input_file)?;
-    let code = input.to_string().unwrap();
+    let input = Input::from_args()?;
 
     // Part 1.
-    let mut p = Program::from(code.as_str());
+    let mut p: Program = Program::parse(&input)?;
     let output = p.input(1).execute()?;
     for line in output {
         println!("{}", line);
     }
 
     // Part 2.
-    let output = p.reset(code.as_str()).input(5).execute()?;
+    let output = p.reset_to_initial().input(5).execute()?;
     for line in output {
         println!("{}", line);
     }
@@ -81,21 +81,12 @@
 
 use aoc::Input;
 use std::collections::HashMap;
-use std::io::{Error, ErrorKind, Result};
+use std::io::Result;
 use std::iter::successors;
 
 fn main() -> Result<()> {
-    let in_file: String = match std::env::args().nth(1) {
-        Some(v) => v,
-        None => {
-            return Err(Error::new(
-                ErrorKind::InvalidInput,
-                "no input file specified",
-            ));
-        }
-    };
-    let input = Input::from_file(in_file)?;
-    let lines = input.lines().unwrap();
+    let input = Input::from_args()?;
+    let lines = input.lines();
 
     // Collect the orbits from the map as a series of associations, A->B.
     let assocs: Vec<Assoc<&str>> = lines
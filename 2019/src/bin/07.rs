@@ -1,6 +1,6 @@
 use aoc::{
     Input,
-    intcode::Program,
+    intcode::{Network, Program},
 };
 use rand::{
     seq::SliceRandom,
@@ -9,25 +9,25 @@ use rand::{
 use std::collections::HashMap;
 use std::io::Result;
 use std::iter::FromIterator;
+use std::sync::mpsc::channel;
 
 fn main() -> Result<()> {
-    let inpath: String = match std::env::args().nth(1) {
-        Some(v) => v,
-        None => { panic!("no input file provided"); },
-    };
-    let input = Input::from_file(inpath).expect("failed to load input file");
-    let code = input.to_string().expect("failed to load intcode");
-
-    let (sequence, signal) = part1(&code)?;
+    let input = Input::from_args()?;
+    let program = Program::parse(&input)?;
+
+    let (sequence, signal) = part1(&program)?;
     println!("{} => {}", sequence, signal);
 
-    let (sequence, signal) = part2(&code)?;
+    let (sequence, signal) = part2(&program)?;
     println!("{} => {}", sequence, signal);
 
+    let (sequence, signal) = part2_threaded(&program)?;
+    println!("{} => {} (threaded)", sequence, signal);
+
     Ok(())
 }
 
-fn part1(code: &str) -> Result<(i32, i64)> {
+fn part1(program: &Program) -> Result<(i32, i64)> {
     let sequence: Vec<i64> = vec![0, 1, 2, 3, 4];
 
     // Store the attempted sequences, and their results.
@@ -53,31 +53,31 @@ fn part1(code: &str) -> Result<(i32, i64)> {
         }
 
         // Amplifiers.
-        let mut a = Program::from(code);
+        let mut a = program.clone();
         a.input(seq[0]);
         a.input(0);
         let out = a.execute()?;
         let signal: i64 = out[0];
 
-        let mut b = Program::from(code);
+        let mut b = program.clone();
         b.input(seq[1]);
         b.input(signal);
         let out = b.execute()?;
         let signal: i64 = out[0];
 
-        let mut c = Program::from(code);
+        let mut c = program.clone();
         c.input(seq[2]);
         c.input(signal);
         let out = c.execute()?;
         let signal: i64 = out[0];
 
-        let mut d = Program::from(code);
+        let mut d = program.clone();
         d.input(seq[3]);
         d.input(signal);
         let out = d.execute()?;
         let signal: i64 = out[0];
 
-        let mut e = Program::from(code);
+        let mut e = program.clone();
         e.input(seq[4]);
         e.input(signal);
         let out = e.execute()?;
@@ -99,7 +99,19 @@ fn part1(code: &str) -> Result<(i32, i64)> {
     Ok((sequence, max))
 }
 
-fn part2(code: &str) -> Result<(i32, i64)> {
+fn part2(program: &Program) -> Result<(i32, i64)> {
+    max_feedback_signal(program, feedback_loop)
+}
+
+// Same as part 2, but each amplifier runs on its own thread.
+fn part2_threaded(program: &Program) -> Result<(i32, i64)> {
+    max_feedback_signal(program, feedback_loop_threaded)
+}
+
+fn max_feedback_signal(
+    program: &Program,
+    run: fn(&Program, &[i64]) -> Result<i64>,
+) -> Result<(i32, i64)> {
     let sequence: Vec<i64> = vec![5, 6, 7, 8, 9];
 
     // Store the attempted sequences, and their results.
@@ -124,63 +136,7 @@ fn part2(code: &str) -> Result<(i32, i64)> {
             continue;
         }
 
-        eprintln!("{:?}", &seq);
-
-        // Amplifiers.
-        let mut a = Program::from(code);
-        let mut b = Program::from(code);
-        let mut c = Program::from(code);
-        let mut d = Program::from(code);
-        let mut e = Program::from(code);
-
-        let mut max_signal: i64 = 0;
-        let mut inputs: Vec<i64> = vec![0];
-        loop {
-            a.input(seq[0]);
-            for i in inputs {
-                a.input(i);
-            }
-            let out = a.execute()?;
-            dbg!(&out);
-
-            b.input(seq[1]);
-            for i in out {
-                b.input(i);
-            }
-            let out = b.execute()?;
-            dbg!(&out);
-
-            c.input(seq[2]);
-            for i in out {
-                c.input(i);
-            }
-            let out = c.execute()?;
-            dbg!(&out);
-
-            d.input(seq[3]);
-            for i in out {
-                d.input(i);
-            }
-            let out = d.execute()?;
-            dbg!(&out);
-
-            e.input(seq[4]);
-            for i in out {
-                e.input(i);
-            }
-            let out = e.execute()?;
-            dbg!(&out);
-
-            let signal: i64 = out[out.len()-1];
-            if signal > max_signal {
-                max_signal = signal;
-            } else if signal == max_signal {
-                break;
-            }
-
-            inputs = out;
-        }
-
+        let max_signal = run(program, &seq)?;
         m.insert(key, max_signal);
     }
 
@@ -197,17 +153,66 @@ fn part2(code: &str) -> Result<(i32, i64)> {
     Ok((sequence, max))
 }
 
+// Runs the amplifiers, wired up in a feedback loop, with the given phase
+// settings, and returns the last signal sent by amplifier E.
+fn feedback_loop(program: &Program, seq: &[i64]) -> Result<i64> {
+    let amplifiers = seq
+        .iter()
+        .map(|&phase| {
+            let mut program = program.clone();
+            program.input(phase);
+            program
+        })
+        .collect();
+    let mut network = Network::ring(amplifiers);
+    network.input(0, 0);
+    network.run()?;
+    Ok(*network.output().last().expect("no output from amplifier E"))
+}
+
+// Same as `feedback_loop`, but each amplifier runs on its own thread, and
+// this thread passes the signal from amplifier E back to amplifier A.
+fn feedback_loop_threaded(program: &Program, seq: &[i64]) -> Result<i64> {
+    let (first, mut rx) = channel();
+    let mut handles = vec![];
+    for &phase in seq {
+        let mut program = program.clone();
+        program.input(phase);
+        let (tx, next) = channel();
+        handles.push(program.spawn(rx, tx));
+        rx = next;
+    }
+
+    first.send(0).expect("amplifier A hung up");
+    let mut signal = 0;
+    for v in rx {
+        signal = v;
+        let _ = first.send(v);
+    }
+
+    for handle in handles {
+        handle.join().expect("amplifier panicked")?;
+    }
+    Ok(signal)
+}
+
 #[test]
 fn test_part2_18216() {
-    let code = String::from("3,52,1001,52,-5,52,3,53,1,52,56,54,1007,54,5,55,1005,55,26,1001,54,-5,54,1105,1,12,1,53,54,53,1008,54,0,55,1001,55,1,55,2,53,55,53,4,53,1001,56,-1,56,1005,56,6,99,0,0,0,0,10");
-    let (_, signal) = part2(&code).unwrap();
+    let program = Program::from("3,52,1001,52,-5,52,3,53,1,52,56,54,1007,54,5,55,1005,55,26,1001,54,-5,54,1105,1,12,1,53,54,53,1008,54,0,55,1001,55,1,55,2,53,55,53,4,53,1001,56,-1,56,1005,56,6,99,0,0,0,0,10");
+    let (_, signal) = part2(&program).unwrap();
     assert_eq!(signal, 18216);
 }
 
 
 #[test]
 fn test_part2_139629729() {
-    let code = String::from("3,26,1001,26,-4,26,3,27,1002,27,2,27,1,27,26,27,4,27,1001,28,-1,28,1005,28,6,99,0,0,5");
-    let (_, signal) = part2(&code).unwrap();
+    let program = Program::from("3,26,1001,26,-4,26,3,27,1002,27,2,27,1,27,26,27,4,27,1001,28,-1,28,1005,28,6,99,0,0,5");
+    let (_, signal) = part2(&program).unwrap();
     assert_eq!(signal, 139629729);
 }
+
+#[test]
+fn test_part2_threaded() {
+    let program = Program::from("3,26,1001,26,-4,26,3,27,1002,27,2,27,1,27,26,27,4,27,1001,28,-1,28,1005,28,6,99,0,0,5");
+    assert_eq!(part2_threaded(&program).unwrap(), part2(&program).unwrap());
+}
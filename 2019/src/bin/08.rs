@@ -1,17 +1,8 @@
 use aoc::Input;
-use std::io::{Error, ErrorKind, Result};
+use std::io::Result;
 
 fn main() -> Result<()> {
-    let in_file: String = match std::env::args().nth(1) {
-        Some(v) => v,
-        None => {
-            return Err(Error::new(
-                ErrorKind::InvalidInput,
-                "no input file specified",
-            ));
-        }
-    };
-    let input = Input::from_file(in_file)?.to_string().expect("no input");
+    let input = Input::from_args()?;
     let input: Vec<u32> = input
         .as_str()
         .chars()
@@ -1,20 +1,10 @@
 use aoc::{intcode::Program, Input};
-use std::io::{Error, ErrorKind, Result};
+use std::io::Result;
 
 fn main() -> Result<()> {
-    let in_file: String = match std::env::args().nth(1) {
-        Some(v) => v,
-        None => {
-            return Err(Error::new(
-                ErrorKind::InvalidInput,
-                "no input file specified",
-            ));
-        }
-    };
-    let input = Input::from_file(in_file)?;
+    let input = Input::from_args()?;
 
-    let code = input.to_string().unwrap();
-    let mut program = Program::from(code.as_str());
+    let mut program: Program = Program::parse(&input)?;
 
     // Part 1.
     let out = program.input(1).execute()?;
@@ -23,7 +13,7 @@ fn main() -> Result<()> {
     }
 
     // Part 2.
-    let out = program.reset(code.as_str()).input(2).execute()?;
+    let out = program.reset_to_initial().input(2).execute()?;
     for line in out {
         println!("{}", line);
     }
@@ -2,20 +2,11 @@ use aoc::{
     day10::{Angle, Map, Position},
     Input,
 };
-use std::io::{Error, ErrorKind, Result};
+use std::io::Result;
 
 fn main() -> Result<()> {
-    let in_file: String = match std::env::args().nth(1) {
-        Some(v) => v,
-        None => {
-            return Err(Error::new(
-                ErrorKind::InvalidInput,
-                "no input file specified",
-            ));
-        }
-    };
-    let input = Input::from_file(in_file)?;
-    let map = Map::from(input.lines().unwrap());
+    let input = Input::from_args()?;
+    let map = Map::from(input.lines());
     let asteroids = map.asteroids();
 
     let (num_asteroids, asteroid) = part1(&asteroids);
@@ -91,7 +82,7 @@ fn test_part1_a() {
 .##.#..###
 ##...#..#.
 .#....####"#).unwrap();
-    let map = Map::from(input.lines().unwrap());
+    let map = Map::from(input.lines());
     println!("{}", &map);
 
     let (x, y) = map.size();
@@ -115,7 +106,7 @@ fn test_part1_b() {
 ..##....##
 ......#...
 .####.###."#).unwrap();
-    let map = Map::from(input.lines().unwrap());
+    let map = Map::from(input.lines());
     let asteroids = map.asteroids();
     let (visible, position) = part1(&asteroids);
     assert_eq!(visible, 35);
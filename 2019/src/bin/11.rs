@@ -5,25 +5,16 @@ use aoc::{
         Stop,
     },
 };
-use std::io::{Error, ErrorKind, Result};
+use std::io::Result;
 
 fn main() -> Result<()> {
-    let in_file: String = match std::env::args().nth(1) {
-        Some(v) => v,
-        None => {
-            return Err(Error::new(
-                ErrorKind::InvalidInput,
-                "no input file specified",
-            ));
-        }
-    };
-    let input = Input::from_file(in_file)?;
-    let code = input.to_string().unwrap();
+    let input = Input::from_args()?;
+    let program = Program::parse(&input)?;
 
-    let panels_painted = part1(&code)?;
+    let panels_painted = part1(&program)?;
     println!("{}", panels_painted);
 
-    part2(&code)?;
+    part2(&program)?;
 
     Ok(())
 }
@@ -44,11 +35,11 @@ fn main() -> Result<()> {
 /// After the robot turns, it should move forward one panel.
 ///
 /// Do not restart the program between runs.
-fn part1(code: &str) -> Result<usize> {
+fn part1(program: &Program) -> Result<usize> {
     use aoc::day10::Position;
     use std::collections::HashMap;
 
-    let mut program = Program::from(code);
+    let mut program = program.clone();
     let mut panels: HashMap<Position, Color> = HashMap::new();
     let mut position = Position::new(0, 0);
     let mut direction = Direction::Up;
@@ -103,11 +94,11 @@ fn part1(code: &str) -> Result<usize> {
 
 /// Same as part 1, but the default panel colour is white.
 /// Draw out the result of the what the robot painted.
-fn part2(code: &str) -> Result<()> {
+fn part2(program: &Program) -> Result<()> {
     use aoc::day10::Position;
     use std::collections::HashMap;
 
-    let mut program = Program::from(code);
+    let mut program = program.clone();
     let mut panels: HashMap<Position, Color> = HashMap::new();
     let mut position = Position::new(0, 0);
     let mut direction = Direction::Up;
@@ -1,26 +1,12 @@
 use aoc::Input;
 use std::fmt;
-use std::io::{Error, ErrorKind, Result};
+use std::io::{Error, Result};
 use std::iter;
 use std::str::FromStr;
 
 fn main() -> Result<()> {
-    let in_file = match std::env::args().nth(1) {
-        Some(v) => v,
-        None => {
-            return Err(Error::new(
-                ErrorKind::InvalidInput,
-                "no input file specified",
-            ));
-        }
-    };
-    let input = Input::from_file(in_file)?;
-    let moons: Vec<Moon> = input
-        .lines()
-        .unwrap()
-        .iter()
-        .map(|ln| Moon::from_str(ln).expect("failed to parse moon"))
-        .collect();
+    let input = Input::from_args()?;
+    let moons: Vec<Moon> = input.parse_lines()?;
 
     println!("{}", run_simulation(moons.clone(), 1000));
     println!("{}", find_period(moons.clone()));
@@ -298,9 +284,7 @@ fn test_simulation_1() {
 <x=3, y=5, z=-1>"#,
     );
     let moons: Vec<Moon> = input
-        .lines()
-        .unwrap()
-        .iter()
+        .lines_iter()
         .map(|ln| Moon::from_str(ln).expect("failed to parse moon"))
         .collect();
 
@@ -316,9 +300,7 @@ fn test_simulation_2() {
 <x=9, y=-8, z=-3>"#,
     );
     let moons: Vec<Moon> = input
-        .lines()
-        .unwrap()
-        .iter()
+        .lines_iter()
         .map(|ln| Moon::from_str(ln).expect("failed to parse moon"))
         .collect();
     assert_eq!(run_simulation(moons, 100), 1940);
@@ -333,9 +315,7 @@ fn test_find_period_1() {
 <x=3, y=5, z=-1>"#,
     );
     let moons: Vec<Moon> = input
-        .lines()
-        .unwrap()
-        .iter()
+        .lines_iter()
         .map(|ln| Moon::from_str(ln).expect("failed to parse moon"))
         .collect();
     assert_eq!(find_period(moons), 2772);
@@ -350,9 +330,7 @@ fn test_find_period_2() {
 <x=9, y=-8, z=-3>"#,
     );
     let moons: Vec<Moon> = input
-        .lines()
-        .unwrap()
-        .iter()
+        .lines_iter()
         .map(|ln| Moon::from_str(ln).expect("failed to parse moon"))
         .collect();
     assert_eq!(find_period(moons), 4686774924);
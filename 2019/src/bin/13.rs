@@ -4,21 +4,11 @@ use aoc::{
 };
 use std::collections::HashMap;
 use std::fmt;
-use std::io::{Error, ErrorKind, Result};
+use std::io::Result;
 
 fn main() -> Result<()> {
-    let in_file: String = match std::env::args().nth(1) {
-        Some(v) => v,
-        None => {
-            return Err(Error::new(
-                ErrorKind::InvalidInput,
-                "no input file specified",
-            ));
-        }
-    };
-    let input = Input::from_file(in_file)?;
-    let code = input.to_string().unwrap();
-    let mut program = Program::from(code.as_str());
+    let input = Input::from_args()?;
+    let mut program: Program = Program::parse(&input)?;
 
     // Part 1
     let output = Output::from(program.execute()?);
@@ -26,7 +16,7 @@ fn main() -> Result<()> {
     println!("Number of block tiles: {}", output.num_blocks());
 
     // Part 2
-    program.reset(code.as_str()).set_mem(0, 2);
+    program.reset_to_initial().set_mem(0, 2);
     program.input(0);
     loop {
         let output = Output::from(program.execute()?);
@@ -44,6 +34,9 @@ fn main() -> Result<()> {
                     program.input(0);
                 }
             }
+            stop => {
+                panic!("unexpected stop: {:?}", stop);
+            }
         }
     }
 
@@ -1,40 +1,32 @@
 use aoc::{
+    geom::Point,
     intcode::{Program, Stop},
     Input,
 };
 use std::io::{Error, ErrorKind, Result};
 
 fn main() -> Result<()> {
-    let in_file: String = match std::env::args().nth(1) {
-        Some(v) => v,
-        None => {
-            return Err(Error::new(
-                ErrorKind::InvalidInput,
-                "no input file specified",
-            ));
-        }
-    };
-    let input = Input::from_file(in_file)?;
-    let code = input.to_string().unwrap();
+    let input = Input::from_args()?;
+    let program = Program::parse(&input)?;
 
     // Part 1
     println!(
         "Number of points affected by the tractor beam: {}",
-        map_tractor_beam(code.as_str(), 50, 50)?
+        map_tractor_beam(&program, 50, 50)?
     );
 
     // Part 2
-    println!("Closest point: {}", part2(code.as_str())?);
+    println!("Closest point: {}", part2(&program)?);
 
     Ok(())
 }
 
-// Runs the given intcode program `code`, and probes each coordinate in the
+// Runs the given intcode program, and probes each coordinate in the
 // given `ax, ay` area to calculate how many points are affected by the tractor
 // beam.
-fn map_tractor_beam(code: &str, ax: isize, ay: isize) -> Result<isize> {
-    let mut program = Program::from(code);
-    let mut output: Vec<isize> = vec![];
+fn map_tractor_beam(program: &Program, ax: i64, ay: i64) -> Result<i64> {
+    let mut program = program.clone();
+    let mut output: Vec<i64> = vec![];
     for y in 0..ay {
         for x in 0..ax {
             program.input(x);
@@ -46,30 +38,33 @@ fn map_tractor_beam(code: &str, ax: isize, ay: isize) -> Result<isize> {
                 }
                 Stop::HCF => {
                     output.extend(out.iter());
-                    program.reset(code);
+                    program.reset_to_initial();
+                }
+                stop => {
+                    panic!("unexpected stop: {:?}", stop);
                 }
             };
         }
     }
-    let num_points: isize = output.iter().filter(|&&n| n == 1).sum();
+    let num_points: i64 = output.iter().filter(|&&n| n == 1).sum();
     Ok(num_points)
 }
 
 // Find the point closest to the tractor beam emitter that would allow for a
 // 100x100 ship to fit completely inside of it, then return those coordinates.
-fn part2(code: &str) -> Result<isize> {
+fn part2(program: &Program) -> Result<i64> {
     let mut x = 0;
-    for y in 100..isize::max_value() {
+    for y in 100..i64::max_value() {
         // Advance the X-position until we are within the beam.
         // NOTE: This tracks the bottom-left coordinate of the 100x100 square.
-        while !point_in_beam(code, &Point { x, y })? {
+        while !point_in_beam(program, &Point { x, y })? {
             x += 1;
         }
 
         // Check to see if the top-right corner of the square is also within
         // the beam.
         if point_in_beam(
-            code,
+            program,
             &Point {
                 x: x + 99,
                 y: y - 99,
@@ -81,8 +76,9 @@ fn part2(code: &str) -> Result<isize> {
     Err(Error::new(ErrorKind::Other, "map not large enough"))
 }
 
-fn point_in_beam(code: &str, point: &Point) -> Result<bool> {
-    let mut program = Program::from(code);
+// Probes the given `point` with a fresh copy of `program`.
+fn point_in_beam(program: &Program, point: &Point) -> Result<bool> {
+    let mut program = program.clone();
     program.input(point.x);
     program.input(point.y);
     let out = program.execute()?;
@@ -92,33 +88,9 @@ fn point_in_beam(code: &str, point: &Point) -> Result<bool> {
     Ok(out[0] == 1)
 }
 
-#[derive(PartialEq, Eq, Hash, Clone, Copy)]
-struct Point {
-    x: isize,
-    y: isize,
-}
-
-use std::fmt;
-
-impl fmt::Display for Point {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "({}, {})", self.x, self.y)
-    }
-}
-
-impl std::ops::Add for Point {
-    type Output = Self;
-    fn add(self, other: Self) -> Self {
-        Self {
-            x: self.x + other.x,
-            y: self.y + other.y,
-        }
-    }
-}
-
 #[test]
 fn debug_location_program() {
     let input = Input::from_file("input/19").unwrap();
-    let code = input.to_string().unwrap();
+    let code = input.to_string();
     let mut program = Program::from(code.as_str());
 }
@@ -0,0 +1,32 @@
+// Disassemble an intcode program, so it can be read.
+//
+// Usage: disasm [--dot] [input file]
+//
+// The program is read from standard input if no input file is given.
+//
+// With --dot, the program's control-flow graph is printed in Graphviz's DOT
+// language instead, with only the reachable instructions in it.
+use aoc::{intcode::Program, Input};
+use std::io::Result;
+
+fn main() -> Result<()> {
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let dot = args.first().is_some_and(|arg| arg == "--dot");
+    if dot {
+        args.remove(0);
+    }
+    let input = match args.into_iter().next() {
+        Some(path) => Input::from_file(path)?,
+        None => Input::from_stdin()?,
+    };
+    let program: Program = Program::parse(&input)?;
+    if dot {
+        print!("{}", program.analyze().to_dot());
+        return Ok(());
+    }
+    for line in program.disassemble() {
+        println!("{}", line);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,82 @@
+// Play an ASCII intcode program, like the text adventure in day 25, from the
+// terminal.
+//
+// Usage: play <input file>
+//
+// The program is always read from a file, and "-" is not allowed, since
+// stdin is where the lines sent to the program come from.
+//
+// Everything the program outputs is printed to stdout, and each line read
+// from stdin is sent to the program. Any output value that is not an ASCII
+// character is printed on a line of its own.
+//
+// Two lines are commands, rather than input: ":save <file>" saves the
+// program's state to a file, and ":load <file>" picks up from a saved state.
+use aoc::{
+    intcode::{Program, Terminal},
+    Input,
+};
+use std::fs;
+use std::io::{self, BufRead, Error, ErrorKind, Result, Write};
+
+fn main() -> Result<()> {
+    let in_file: String = match std::env::args().nth(1) {
+        Some(v) => v,
+        None => {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "usage: play <input file>",
+            ));
+        }
+    };
+    if in_file == "-" {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "play reads its input from stdin, so the program must be in a file",
+        ));
+    }
+    let input = Input::from_file(in_file)?;
+    let mut terminal = Terminal::new(Program::parse(&input)?);
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    loop {
+        let output = terminal.read_until_prompt()?;
+        print!("{}", output.text);
+        for v in output.values {
+            println!("{}", v);
+        }
+        io::stdout().flush()?;
+
+        if terminal.is_halted() {
+            break;
+        }
+        let line = match lines.next() {
+            Some(line) => line?,
+            None => break,
+        };
+        if let Some(path) = line.strip_prefix(":save ") {
+            match fs::write(path, terminal.program().save()) {
+                Ok(()) => eprintln!("saved to {}", path),
+                Err(e) => eprintln!("cannot save to {}: {}", path, e),
+            }
+        } else if let Some(path) = line.strip_prefix(":load ") {
+            match load(path) {
+                Ok(program) => {
+                    *terminal.program_mut() = program;
+                    eprintln!("loaded {}", path);
+                }
+                Err(e) => eprintln!("cannot load {}: {}", path, e),
+            }
+        } else if let Err(e) = terminal.send_line(&line) {
+            eprintln!("cannot send {:?}: {}", line, e);
+        }
+    }
+
+    Ok(())
+}
+
+// Loads a program's state saved with ":save".
+fn load(path: &str) -> Result<Program> {
+    let saved = fs::read_to_string(path)?;
+    Ok(Program::load(&saved)?)
+}
@@ -0,0 +1,58 @@
+// Run an intcode program, writing a trace of every instruction it executes
+// to a file, and print a profile of the run.
+//
+// Usage: trace <input file> <trace file> [input values...]
+//
+// An input file of "-" reads the program from stdin. The trace file cannot
+// be left out, as it would be ambiguous with the input values.
+//
+// The program runs until it halts, or until it needs more input than was
+// given on the command line. Its output is printed to stdout, and the profile
+// to stderr.
+use aoc::{
+    intcode::{Profile, Program, TraceWriter},
+    Input,
+};
+use std::io::{Error, ErrorKind, Result};
+use std::sync::{Arc, Mutex};
+
+fn main() -> Result<()> {
+    let mut args = std::env::args().skip(1);
+    let (in_file, trace_file) = match (args.next(), args.next()) {
+        (Some(a), Some(b)) => (a, b),
+        _ => {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "usage: trace <input file> <trace file> [input values...]",
+            ));
+        }
+    };
+    let input = Input::from_file(in_file)?;
+    let mut program: Program = Program::parse(&input)?;
+    for arg in args {
+        let v = arg
+            .parse()
+            .map_err(|e| Error::new(ErrorKind::InvalidInput, e))?;
+        program.input(v);
+    }
+
+    let trace = Arc::new(Mutex::new(TraceWriter::create(trace_file)?));
+    let profile = Arc::new(Mutex::new(Profile::new()));
+    program.set_tracer((trace.clone(), profile.clone()));
+    let output = program.execute()?;
+
+    // Detach the tracers, so that the trace writer can be finished, and any
+    // error writing the trace reported.
+    drop(program.take_tracer());
+    if let Ok(trace) = Arc::try_unwrap(trace) {
+        trace.into_inner().unwrap().finish()?;
+    }
+
+    for v in output {
+        println!("{}", v);
+    }
+    eprintln!("Stopped: {:?}", program.reason_for_stop().unwrap());
+    eprint!("{}", profile.lock().unwrap());
+
+    Ok(())
+}
@@ -1,4 +1,56 @@
-use std::io::{Error, ErrorKind, Result};
+use std::collections::{HashSet, VecDeque};
+use std::error;
+use std::fmt;
+use std::io;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use crate::{Input, InputError};
+
+mod analysis;
+mod ascii;
+mod asm;
+mod decode;
+mod dialect;
+mod disasm;
+mod future;
+#[cfg(test)]
+mod fuzz;
+mod history;
+mod memory;
+mod network;
+mod save;
+mod stream;
+mod thread;
+mod trace;
+mod word;
+
+pub use analysis::{Analysis, Block, CodeWrite, Edge, Exit, Region};
+pub use ascii::{AsciiError, Screen, Terminal, Text};
+pub use asm::{assemble, disassemble, AsmError, AsmErrorKind};
+pub use dialect::{Args, Dialect, Opcode};
+pub use disasm::{Disassembler, Line};
+pub use future::{AsyncProgram, InputSink};
+pub use memory::Backend;
+pub use network::{Nat, NetStop, Network, NetworkError, Packet};
+pub use save::LoadError;
+pub use stream::{Blocking, InputFn, IntcodeInput, IntcodeOutput, OutputFn};
+pub use thread::Finished;
+pub use trace::{Event, Loop, MemoryWrite, Operand, Profile, TraceWriter, Tracer};
+pub use word::Word;
+
+use decode::{Cache, Stale};
+use dialect::{Custom, MAX_PARAMS};
+use history::History;
+use memory::Memory;
+use stream::NoInput;
+use trace::Hook;
+
+/// The most memory, in words, a program can address.
+/// An address at or past this is an `IntcodeError::AddressOverflow`, so that
+/// a stray address cannot grow memory without bound when the program has no
+/// memory limit.
+pub const MAX_MEMORY: usize = 1 << 24;
 
 /// An [intcode](https://adventofcode.com/2019/day/2) program.
 ///
@@ -8,7 +60,7 @@ use std::io::{Error, ErrorKind, Result};
 /// * `&str`
 /// * `String`
 /// * `Vec<String>`
-/// * `Vec<isize>`
+/// * `Vec<W>`
 ///
 /// The implementations for `&str` and `String` expect the same input.
 /// The input for these intcode puzzles should be a single line, of
@@ -16,33 +68,90 @@ use std::io::{Error, ErrorKind, Result};
 ///
 /// The implementation for `Vec<String>` is expected to be the input, split on
 /// each "," character.
-pub struct Program {
-    // Initial state of the program.
-    mem: Vec<isize>,
+///
+/// The `from` implementations for strings panic if a value cannot be parsed;
+/// use `Program::parse`, or `str::parse`, to get an error instead.
+///
+/// By default, each cell of a program's memory is an `i64`, and the `from`
+/// implementations for strings produce a `Program<i64>`.
+/// Programs with any other `Word` type, such as `i128` or `num::BigInt`, can
+/// be built from a `Vec<W>`, or parsed from a string with `str::parse`.
+///
+/// Cloning a `Program` is cheap enough to branch from a saved state when
+/// searching, since its initial memory is shared between clones.
+/// Clones do not inherit the program's tracer, if it has one.
+#[derive(Clone)]
+pub struct Program<W = i64> {
+    // Initial state of the program, shared with any clones, so it can be
+    // restored by `reset_to_initial`.
+    initial: Arc<Vec<W>>,
+
+    // Current state of the program's memory.
+    mem: Memory<W>,
+
+    // The program's initial memory, decoded into instructions.
+    decoded: Cache<W>,
+
+    // The opcodes the program understands, shared with any clones.
+    dialect: Arc<Dialect<W>>,
 
     // Any input to the program.
-    input: Option<Vec<isize>>,
+    input: VecDeque<W>,
 
     // The relative base for relative parameters.
     // By default, the relative base is zero.
-    rel_base: usize,
+    rel_base: W,
+
+    // Whether arithmetic overflow is an error, rather than wrapping around.
+    checked: bool,
 
     // Instruction pointer.
     ip: usize,
 
     // The reason the program has stopped running.
     reason: Option<Stop>,
+
+    // Instruction pointer addresses `run_until` should pause at.
+    breakpoints: HashSet<usize>,
+
+    // Memory addresses that `run_until` should pause after writing to.
+    watchpoints: HashSet<usize>,
+
+    // Handed an event for every instruction executed, if set.
+    tracer: Hook<W>,
+
+    // The highest address the program may use, plus one, if limited.
+    memory_limit: Option<usize>,
+
+    // The most instructions the program may execute, and values it may
+    // output, if limited.
+    instruction_limit: Option<u64>,
+    output_limit: Option<u64>,
+
+    // The number of instructions executed, and values output, since the
+    // program was loaded or reset.
+    executed: u64,
+    outputs: u64,
+
+    // The undo log of the instructions executed, if it is being recorded.
+    history: Option<History<W>>,
 }
 
 impl From<&str> for Program {
     fn from(s: &str) -> Self {
-        Self {
-            mem: Self::mem_from_str(s),
-            input: None,
-            rel_base: 0,
-            ip: 0,
-            reason: None,
-        }
+        Self::from(Self::mem_from_str(s))
+    }
+}
+
+impl<W: Word> FromStr for Program<W> {
+    type Err = W::Err;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mem = s
+            .split(',')
+            .map(str::parse)
+            .collect::<Result<Vec<W>, _>>()?;
+        Ok(Self::from(mem))
     }
 }
 
@@ -54,215 +163,412 @@ impl From<String> for Program {
 
 impl From<Vec<String>> for Program {
     fn from(v: Vec<String>) -> Self {
-        Self {
-            mem: v
-                .iter()
-                .map(|s| {
-                    let n: isize = s.parse().unwrap();
-                    n
-                })
-                .collect(),
-            input: None,
-            rel_base: 0,
-            ip: 0,
-            reason: None,
-        }
+        Self::from(Self::mem_from_str(&v.join(",")))
     }
 }
 
-impl From<Vec<isize>> for Program {
-    fn from(v: Vec<isize>) -> Self {
+impl<W: Word> From<Vec<W>> for Program<W> {
+    fn from(v: Vec<W>) -> Self {
+        let dialect = Arc::new(Dialect::new());
         Self {
-            mem: v,
-            input: None,
-            rel_base: 0,
+            initial: Arc::new(v.clone()),
+            decoded: Cache::new(&v, &dialect),
+            dialect,
+            mem: Memory::from(v),
+            input: VecDeque::new(),
+            rel_base: W::from_i64(0),
+            checked: false,
             ip: 0,
             reason: None,
+            breakpoints: HashSet::new(),
+            watchpoints: HashSet::new(),
+            tracer: Hook::default(),
+            memory_limit: None,
+            instruction_limit: None,
+            output_limit: None,
+            executed: 0,
+            outputs: 0,
+            history: None,
         }
     }
 }
 
-impl Program {
-    fn mem_from_str(s: &str) -> Vec<isize> {
+impl<W: Word> Program<W> {
+    fn mem_from_str(s: &str) -> Vec<W> {
         s.split(",")
-            .map(|v| {
-                let n: isize = v.parse().unwrap();
-                n
+            .map(|v| match v.parse() {
+                Ok(n) => n,
+                Err(_) => panic!("invalid intcode value: {:?}", v),
             })
             .collect()
     }
 
+    /// Parses a program from the puzzle input, which holds comma-separated
+    /// values.
+    ///
+    /// Unlike the `from` implementations for strings, a value that cannot be
+    /// parsed is an error, which gives its line and column.
+    pub fn parse(input: &Input) -> Result<Self, InputError>
+    where
+        W::Err: fmt::Display,
+    {
+        Ok(Self::from(input.parse_csv()?))
+    }
+
     /// Set the input for the program to `v`.
     /// This is typically called before `execute`.
-    pub fn input(&mut self, v: isize) -> &mut Self {
-        if let Some(input) = &mut self.input {
-            input.push(v);
-            return self;
-        }
-
-        let input: Vec<isize> = vec![v];
-        self.input = Some(input);
+    pub fn input(&mut self, v: W) -> &mut Self {
+        self.input.push_back(v);
         self
     }
 
-    /// Consumes the first input value provided via the `input` method.
-    /// Successive calls to `get_input` will drain any input provided to the
-    /// program before `execute` or `execute_mut` were called.
-    fn take_input(&mut self) -> Option<isize> {
-        if let Some(input) = &mut self.input {
-            if input.len() == 0 {
-                return None;
+    /// Return the current instruction at the instruction pointer,
+    /// and the position of the next instruction.
+    ///
+    /// The instruction pointer is not advanced, so any error returned while
+    /// decoding refers to the instruction at `self.ip`.
+    fn peek_instruction(&self) -> Result<(Instruction<W>, usize), IntcodeError> {
+        // Figure out how many instructions our current instruction takes,
+        // so we know how far ahead we have to scan, to get to the next
+        // instruction.
+        let word = self.current_word();
+        let np = match self.dialect.num_params(word) {
+            Some(np) => np,
+            None => {
+                return Err(IntcodeError::UnknownOpcode { ip: self.ip, word });
             }
-            return Some(input.remove(0));
+        };
+        let next = self.ip + np + 1;
+        if next > self.mem.len() {
+            return Err(IntcodeError::Truncated { ip: self.ip, word });
         }
-        None
+        let words: [W; 4] = std::array::from_fn(|i| match i {
+            i if i <= np => self.mem.get(self.ip + i),
+            _ => W::from_i64(0),
+        });
+        let inst = Instruction::parse(self.ip, &words[..np + 1], &self.dialect)?;
+        Ok((inst, next))
+    }
+
+    /// Execute runs the program the computer was loaded with, and returns
+    /// the program's output.
+    ///
+    /// If the program contains an instruction that cannot be executed, an
+    /// `IntcodeError` is returned, and the instruction pointer is left
+    /// pointing at the faulting instruction.
+    ///
+    /// Breakpoints and watchpoints are ignored by `execute`; use `run_until`
+    /// to stop on them.
+    pub fn execute(&mut self) -> Result<Vec<W>, IntcodeError> {
+        let mut output: Vec<W> = vec![];
+        self.run(&mut NoInput, &mut output)?;
+        Ok(output)
     }
 
-    /// Returns the next available value for input, or `None` if there is
-    /// no input available.
-    fn peek_input(&self) -> Option<isize> {
-        if let Some(input) = &self.input {
-            if input.len() == 0 {
-                return None;
+    /// Runs the program until it stops, pulling input from `input` only when
+    /// an input instruction needs it, and pushing each value the program
+    /// outputs to `output` as soon as it is produced.
+    ///
+    /// Any input provided via the `input` method is consumed before `input`
+    /// is asked for more.
+    /// Returns the reason the program stopped.
+    pub fn run<I, O>(&mut self, input: &mut I, output: &mut O) -> Result<Stop, IntcodeError>
+    where
+        I: IntcodeInput<W> + ?Sized,
+        O: IntcodeOutput<W> + ?Sized,
+    {
+        loop {
+            match self.step_with(input)? {
+                Step::Output(v) => output.push_output(v),
+                Step::Stopped(s) => return Ok(s),
+                _ => {}
             }
-            return Some(input[0]);
         }
-        None
     }
 
-    /// Return the current instruction at the instruction pointer,
-    /// and the position of the next instruction.
-    fn peek_instruction(&self) -> (Instruction, usize) {
-        // Figure out how many instructions our current instruction takes,
-        // so we know how far ahead we have to scan, to get to the next
-        // instruction.
-        let next = self.ip + Instruction::num_params(self.mem[self.ip]) + 1;
-        let params = self.mem[self.ip..next].to_vec();
-        (Instruction::parse(params), next)
+    /// Runs the program until `pred` returns `true`, a breakpoint or
+    /// watchpoint is hit, or the program stops.
+    ///
+    /// `pred` is called before each instruction is executed, and is given the
+    /// program in its current state.
+    /// Breakpoints are also checked before each instruction, except for the
+    /// very first one, so that calling `run_until` again will continue past
+    /// the breakpoint that was just hit.
+    ///
+    /// The reason for pausing is returned, along with any output the program
+    /// produced along the way.
+    pub fn run_until<F>(&mut self, mut pred: F) -> Result<(Pause<W>, Vec<W>), IntcodeError>
+    where
+        F: FnMut(&Program<W>) -> bool,
+    {
+        let mut output: Vec<W> = vec![];
+        let mut first = true;
+        loop {
+            if pred(self) {
+                return Ok((Pause::Predicate, output));
+            }
+            if !first && self.breakpoints.contains(&self.ip) {
+                return Ok((Pause::Breakpoint(self.ip), output));
+            }
+            first = false;
+
+            match self.step()? {
+                Step::Output(v) => output.push(v),
+                Step::Watchpoint(w) => return Ok((Pause::Watchpoint(w), output)),
+                Step::Stopped(s) => return Ok((Pause::Stopped(s), output)),
+                Step::Executed => {}
+            }
+        }
     }
 
-    /// Return the next instruction, advancing the instruction pointer.
-    fn take_instruction(&mut self) -> Instruction {
-        let (inst, next) = self.peek_instruction();
-        self.ip = next;
-        inst
+    /// Executes exactly one instruction, and returns what happened.
+    ///
+    /// If the instruction at the instruction pointer is an input instruction,
+    /// but there is no input available, the instruction pointer is not
+    /// advanced and `Step::Stopped(Stop::WaitingForInput)` is returned.
+    /// Likewise, the instruction pointer is left on a halt instruction, so
+    /// stepping a halted program keeps returning `Step::Stopped(Stop::HCF)`.
+    pub fn step(&mut self) -> Result<Step<W>, IntcodeError> {
+        self.step_with(&mut NoInput)
     }
 
-    /// Execute runs the program the computer was loaded with, and returns
-    /// the program's output.
-    pub fn execute(&mut self) -> Result<Vec<isize>> {
-        let mut output: Vec<isize> = vec![];
-        loop {
-            // If our next instruction is an Input, but we do not have any
-            // input, halt here, so that the caller can provide some.
-            let (inst, _) = self.peek_instruction();
-            //eprintln!("{: >8}: {:?}", &self.ip, &inst);
-            match inst {
-                Instruction::Input(_p) => match self.peek_input() {
-                    None => {
-                        self.reason = Some(Stop::WaitingForInput);
-                        return Ok(output);
-                    }
-                    _ => {}
-                },
-                _ => {}
+    /// Executes exactly one instruction, like `step`, except that once any
+    /// input provided via the `input` method runs out, input instructions
+    /// pull their input from `input`.
+    ///
+    /// If the program has a tracer, it is handed an `Event` describing the
+    /// instruction once it has been executed.
+    pub fn step_with<I>(&mut self, input: &mut I) -> Result<Step<W>, IntcodeError>
+    where
+        I: IntcodeInput<W> + ?Sized,
+    {
+        let (inst, next) = match self.decoded.get(self.ip) {
+            Some((inst, next)) => (inst.clone(), *next),
+            None => self.peek_instruction()?,
+        };
+        if let Some(stop) = self.check_limits(&inst) {
+            self.reason = Some(stop);
+            return Ok(Step::Stopped(stop));
+        }
+
+        let recording = self.history.is_some();
+        if recording {
+            self.begin_entry();
+        }
+        let step = if self.tracer.is_set() {
+            self.step_traced(inst, next, input)
+        } else {
+            self.execute_instruction(inst, next, input)
+        };
+        if recording {
+            self.end_entry(&step);
+        }
+        let step = step?;
+        match step {
+            Step::Stopped(_) => {}
+            Step::Output(_) => {
+                self.executed += 1;
+                self.outputs += 1;
             }
+            Step::Executed | Step::Watchpoint(_) => self.executed += 1,
+        }
+        Ok(step)
+    }
 
-            // Take the next instruction.
-            let inst = self.take_instruction();
-            match inst {
-                Instruction::Add(n, v, op) => {
-                    let a = self.resolve_param(&n);
-                    let b = self.resolve_param(&v);
-                    let op = self.resolve_out_ptr(&op);
-                    if op >= self.mem.len() {
-                        self.mem.resize(op + 1, 0);
-                    }
-                    self.mem[op] = a + b;
-                }
+    /// Returns the reason the program should stop before executing `inst`,
+    /// the instruction at the instruction pointer, if it would exceed any of
+    /// the program's limits.
+    fn check_limits(&self, inst: &Instruction<W>) -> Option<Stop> {
+        if self.instruction_limit.is_some_and(|n| self.executed >= n) {
+            return Some(Stop::InstructionLimit);
+        }
+        let is_output = matches!(inst, Instruction::Output(_));
+        if is_output && self.output_limit.is_some_and(|n| self.outputs >= n) {
+            return Some(Stop::OutputLimit);
+        }
 
-                Instruction::Multiply(n, v, op) => {
-                    let a = self.resolve_param(&n);
-                    let b = self.resolve_param(&v);
-                    let op = self.resolve_out_ptr(&op);
-                    if op >= self.mem.len() {
-                        self.mem.resize(op + 1, 0);
-                    }
+        let limit = self.memory_limit?;
+        for param in inst.params() {
+            let address = match param {
+                Parameter::Positional(address) => Some(*address),
+                Parameter::Relative(n) => self.relative_address(n).ok(),
+                Parameter::Immediate(_) => None,
+            };
+            if address.is_some_and(|address| address >= limit) {
+                return Some(Stop::MemoryLimit);
+            }
+        }
+        None
+    }
 
-                    self.mem[op] = a * b;
-                }
+    /// Executes `inst`, the instruction at the instruction pointer, where
+    /// `next` is the address of the instruction after it.
+    fn execute_instruction<I>(
+        &mut self,
+        inst: Instruction<W>,
+        next: usize,
+        input: &mut I,
+    ) -> Result<Step<W>, IntcodeError>
+    where
+        I: IntcodeInput<W> + ?Sized,
+    {
+        // Execute the instruction.
+        // The instruction pointer is only advanced once the instruction
+        // has been executed successfully.
+        let step = match inst {
+            Instruction::Add(n, v, op) => {
+                let a = self.resolve_param(&n)?;
+                let b = self.resolve_param(&v)?;
+                let op = self.resolve_out_ptr(&op)?;
+                let sum = self.add(&a, &b)?;
+                self.write_mem(op, sum)
+            }
+
+            Instruction::Multiply(n, v, op) => {
+                let a = self.resolve_param(&n)?;
+                let b = self.resolve_param(&v)?;
+                let op = self.resolve_out_ptr(&op)?;
+                let product = self.mul(&a, &b)?;
+                self.write_mem(op, product)
+            }
 
-                Instruction::Input(p) => {
-                    // We need to resolve the parameter differently than
-                    // `resolve_param`, since the value we want is what's
-                    // contained in `p`, and not resolved by a location in
-                    // `self.mem`.
-                    let op = self.resolve_out_ptr(&p);
-                    if let Some(v) = self.take_input() {
-                        self.set_mem(op, v);
+            Instruction::Input(p) => {
+                // We need to resolve the parameter differently than
+                // `resolve_param`, since the value we want is what's
+                // contained in `p`, and not resolved by a location in
+                // `self.mem`.
+                let op = self.resolve_out_ptr(&p)?;
+
+                // If we do not have any input, halt here, so that the
+                // caller can provide some.
+                match self.input.pop_front().or_else(|| input.next_input()) {
+                    Some(v) => {
+                        if self.history.is_some() {
+                            self.record_input(&v);
+                        }
+                        self.write_mem(op, v)
+                    }
+                    None => {
+                        self.reason = Some(Stop::WaitingForInput);
+                        return Ok(Step::Stopped(Stop::WaitingForInput));
                     }
                 }
+            }
 
-                Instruction::Output(p) => {
-                    let val = self.resolve_param(&p);
-                    output.push(val);
-                }
+            Instruction::Output(p) => {
+                let val = self.resolve_param(&p)?;
+                Step::Output(val)
+            }
 
-                Instruction::JumpIfTrue(n, v) => {
-                    let a = self.resolve_param(&n);
-                    if a != 0 {
-                        let b = self.resolve_param(&v);
-                        self.ip = b as usize;
-                    }
+            Instruction::JumpIfTrue(n, v) => {
+                let a = self.resolve_param(&n)?;
+                if !a.is_zero() {
+                    let b = self.resolve_param(&v)?;
+                    self.ip = self.check_address(&b)?;
+                    return Ok(Step::Executed);
                 }
+                Step::Executed
+            }
 
-                Instruction::JumpIfFalse(n, v) => {
-                    let a = self.resolve_param(&n);
-                    if a == 0 {
-                        let b = self.resolve_param(&v);
-                        self.ip = b as usize;
-                    }
+            Instruction::JumpIfFalse(n, v) => {
+                let a = self.resolve_param(&n)?;
+                if a.is_zero() {
+                    let b = self.resolve_param(&v)?;
+                    self.ip = self.check_address(&b)?;
+                    return Ok(Step::Executed);
                 }
+                Step::Executed
+            }
 
-                Instruction::LessThan(n, v, op) => {
-                    let a = self.resolve_param(&n);
-                    let b = self.resolve_param(&v);
-                    let op = self.resolve_out_ptr(&op);
-                    if a < b {
-                        self.set_mem(op, 1);
-                    } else {
-                        self.set_mem(op, 0);
-                    }
-                }
+            Instruction::LessThan(n, v, op) => {
+                let a = self.resolve_param(&n)?;
+                let b = self.resolve_param(&v)?;
+                let op = self.resolve_out_ptr(&op)?;
+                let v = W::from_i64((a < b) as i64);
+                self.write_mem(op, v)
+            }
 
-                Instruction::Equals(n, v, op) => {
-                    let a = self.resolve_param(&n);
-                    let b = self.resolve_param(&v);
-                    let op = self.resolve_out_ptr(&op);
-                    if a == b {
-                        self.set_mem(op, 1);
-                    } else {
-                        self.set_mem(op, 0);
-                    }
-                }
+            Instruction::Equals(n, v, op) => {
+                let a = self.resolve_param(&n)?;
+                let b = self.resolve_param(&v)?;
+                let op = self.resolve_out_ptr(&op)?;
+                let v = W::from_i64((a == b) as i64);
+                self.write_mem(op, v)
+            }
 
-                Instruction::RelativeBaseOffset(p) => {
-                    let n = self.resolve_param(&p);
-                    self.rel_base = ((self.rel_base as isize) + n) as usize;
-                }
+            Instruction::RelativeBaseOffset(p) => {
+                let n = self.resolve_param(&p)?;
+                self.rel_base = self.add(&self.rel_base, &n)?;
+                Step::Executed
+            }
 
-                Instruction::HCF => {
-                    self.reason = Some(Stop::HCF);
-                    return Ok(output);
-                }
+            Instruction::HCF => {
+                self.reason = Some(Stop::HCF);
+                return Ok(Step::Stopped(Stop::HCF));
+            }
 
-                Instruction::BadInput(v) => {
-                    return Err(Error::new(ErrorKind::InvalidInput, format!("{:?}", v)));
-                }
+            Instruction::Custom(custom) => {
+                return self.execute_custom(&custom, next, input);
             }
+        };
+        self.ip = next;
+        Ok(step)
+    }
+
+    /// Writes `value` to memory at `position` on behalf of the instruction at
+    /// the instruction pointer, reporting the write if `position` is being
+    /// watched.
+    fn write_mem(&mut self, position: usize, value: W) -> Step<W> {
+        if self.history.is_some() {
+            self.record_write(position);
+        }
+        if self.watchpoints.contains(&position) {
+            let old = self.peek_mem(position);
+            self.set_mem(position, value.clone());
+            return Step::Watchpoint(Watch {
+                ip: self.ip,
+                address: position,
+                old,
+                new: value,
+            });
+        }
+        self.set_mem(position, value);
+        Step::Executed
+    }
+
+    /// Returns `a + b`, on behalf of the instruction at the instruction
+    /// pointer.
+    /// Overflow wraps around, unless the program uses checked arithmetic.
+    fn add(&self, a: &W, b: &W) -> Result<W, IntcodeError> {
+        if !self.checked {
+            return Ok(a.wrapping_add(b));
+        }
+        a.checked_add(b).ok_or_else(|| self.overflow())
+    }
+
+    /// Returns `a * b`, on behalf of the instruction at the instruction
+    /// pointer.
+    /// Overflow wraps around, unless the program uses checked arithmetic.
+    fn mul(&self, a: &W, b: &W) -> Result<W, IntcodeError> {
+        if !self.checked {
+            return Ok(a.wrapping_mul(b));
+        }
+        a.checked_mul(b).ok_or_else(|| self.overflow())
+    }
+
+    fn overflow(&self) -> IntcodeError {
+        IntcodeError::Overflow {
+            ip: self.ip,
+            word: self.current_word(),
         }
     }
 
+    // Returns the instruction word at the instruction pointer, for reporting
+    // errors.
+    fn current_word(&self) -> i64 {
+        self.peek_mem(self.ip).saturating_i64()
+    }
+
     // Returns the value for the given param.
     //
     // If `param` is a `Parameter::Positional`, the returned value will be the
@@ -270,27 +576,16 @@ impl Program {
     // If `param` is a `Parameter::Immediate`, that value is returned, without
     // incurring a lookup through `mem`.
     //
-    // # Panics
-    //
-    // This method will panic if `param` is a `Parameter::UnexpectedMode`.
-    fn resolve_param(&mut self, param: &Parameter) -> isize {
+    // An error is returned if a `Parameter::Relative` resolves to a negative
+    // address.
+    fn resolve_param(&mut self, param: &Parameter<W>) -> Result<W, IntcodeError> {
         match param {
-            Parameter::Positional(i) => {
-                if *i >= self.mem.len() {
-                    return 0;
-                }
-                self.mem[*i]
-            }
-            Parameter::Immediate(n) => *n,
+            Parameter::Positional(i) => Ok(self.mem.get(*i)),
+            Parameter::Immediate(n) => Ok(n.clone()),
             Parameter::Relative(n) => {
-                let i = (self.rel_base as isize + n) as usize;
-                if i >= self.mem.len() {
-                    self.mem.resize(i + 1, 0);
-                }
-                self.mem[i]
-            }
-            Parameter::UnexpectedMode(m) => {
-                panic!("unexpected mode: {}", m);
+                let i = self.relative_address(n)?;
+                self.mem.grow(i + 1);
+                Ok(self.mem.get(i))
             }
         }
     }
@@ -298,85 +593,489 @@ impl Program {
     /// Resolves a parameter as though it is a value indicating where to store
     /// output to.
     ///
-    /// # Panics
-    ///
-    /// If `param` is either `Relative` or `UnexpectedMode`.
-    fn resolve_out_ptr(&self, param: &Parameter) -> usize {
+    /// An error is returned if `param` is `Immediate`, or if it resolves to a
+    /// negative address.
+    fn resolve_out_ptr(&self, param: &Parameter<W>) -> Result<usize, IntcodeError> {
         match param {
-            Parameter::Positional(u) => *u,
-            Parameter::Relative(i) => (self.rel_base as isize + i) as usize,
-            Parameter::Immediate(u) => {
-                panic!("resolve immediate output pointer: {}", u);
-            }
-            Parameter::UnexpectedMode(m) => {
-                panic!("unexpected mode: {}", m);
-            }
+            Parameter::Positional(u) => Ok(*u),
+            Parameter::Relative(i) => self.relative_address(i),
+            Parameter::Immediate(_) => Err(IntcodeError::ImmediateWrite {
+                ip: self.ip,
+                word: self.current_word(),
+            }),
         }
     }
 
+    /// Returns the address `n` past the relative base, on behalf of the
+    /// instruction at the instruction pointer.
+    /// Unlike `add`, this never wraps around, even without checked
+    /// arithmetic, since a wrapped address would point somewhere else in
+    /// memory entirely.
+    fn relative_address(&self, n: &W) -> Result<usize, IntcodeError> {
+        let address = self
+            .rel_base
+            .checked_add(n)
+            .ok_or_else(|| self.overflow())?;
+        self.check_address(&address)
+    }
+
+    /// Checks that `address` can be used to index into memory, on behalf of
+    /// the instruction at the instruction pointer.
+    fn check_address(&self, address: &W) -> Result<usize, IntcodeError> {
+        if address.is_negative() {
+            return Err(IntcodeError::NegativeAddress {
+                ip: self.ip,
+                word: self.current_word(),
+                address: address.saturating_i64(),
+            });
+        }
+        address
+            .to_usize()
+            .filter(|&address| address < MAX_MEMORY)
+            .ok_or_else(|| IntcodeError::AddressOverflow {
+                ip: self.ip,
+                word: self.current_word(),
+            })
+    }
+
     /// Set the value of memory at `position` to `value`.
     /// If `position` refers to a location outside of the program's
     /// currently-allocated memory, more memory will be allocated.
     /// Any memory between the old boundary and `position` will be filled with
     /// zeroes.
-    pub fn set_mem(&mut self, position: usize, value: isize) {
-        if position >= self.mem.len() {
-            self.mem.resize(position + 1, 0);
-        }
-        self.mem[position] = value;
+    ///
+    /// # Panics
+    ///
+    /// Panics if `position` is not below `MAX_MEMORY`.
+    pub fn set_mem(&mut self, position: usize, value: W) {
+        assert!(position < MAX_MEMORY, "address {} out of range", position);
+        self.mem.set(position, value);
+        self.decoded.write(position);
     }
 
     /// Resets any internal state, such as the relative base and any input,
     /// and sets the program's initial memory to `code`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a value in `code` cannot be parsed.
     pub fn reset(&mut self, code: &str) -> &mut Self {
+        self.initial = Arc::new(Self::mem_from_str(code));
+        self.decoded.reload(&self.initial, &self.dialect);
+        self.reset_to_initial()
+    }
+
+    /// Resets any internal state, such as the relative base and any input,
+    /// and restores the memory the program was originally loaded with.
+    /// Unlike `reset`, this does not need to parse the program again.
+    pub fn reset_to_initial(&mut self) -> &mut Self {
         self.ip = 0;
-        self.rel_base = 0;
-        self.input = None;
-        self.mem = Self::mem_from_str(code);
+        self.rel_base = W::from_i64(0);
+        self.input.clear();
+        self.reason = None;
+        self.executed = 0;
+        self.outputs = 0;
+        self.mem.load(&self.initial);
+        self.decoded.reset();
+        self.clear_history();
         self
     }
 
-    // Returns the value of memory at the given position, `pos`.
-    pub fn peek_mem(&self, pos: usize) -> isize {
-        if pos >= self.mem.len() {
-            return 0;
+    /// Returns the backend used to store the program's memory.
+    pub fn backend(&self) -> Backend {
+        self.mem.backend()
+    }
+
+    /// Switches the program's memory over to `backend`, keeping its
+    /// contents.
+    pub fn set_backend(&mut self, backend: Backend) -> &mut Self {
+        if self.mem.backend() != backend {
+            self.mem = Memory::new(&self.mem.as_slice(), backend);
         }
-        self.mem[pos]
+        self
+    }
+
+    /// Returns a copy of the program, in its current state, that can be run
+    /// independently of the original.
+    ///
+    /// The program's memory is switched over to `Backend::Paged` first, so
+    /// that the original and the fork share any memory neither of them
+    /// writes to.
+    pub fn fork(&mut self) -> Self {
+        self.set_backend(Backend::Paged);
+        self.clone()
+    }
+
+    /// Returns a snapshot of the program's current state, which can be
+    /// restored later with `restore`.
+    ///
+    /// Breakpoints and watchpoints are not part of the snapshot.
+    pub fn snapshot(&self) -> Snapshot<W> {
+        Snapshot {
+            mem: self.mem.clone(),
+            input: self.input.clone(),
+            rel_base: self.rel_base.clone(),
+            ip: self.ip,
+            reason: self.reason,
+            stale: self.decoded.stale().clone(),
+            executed: self.executed,
+            outputs: self.outputs,
+        }
+    }
+
+    /// Restores the program's state from `snapshot`.
+    pub fn restore(&mut self, snapshot: &Snapshot<W>) -> &mut Self {
+        self.mem = snapshot.mem.clone();
+        self.input.clone_from(&snapshot.input);
+        self.rel_base = snapshot.rel_base.clone();
+        self.ip = snapshot.ip;
+        self.reason = snapshot.reason;
+        self.decoded.set_stale(&snapshot.stale);
+        self.executed = snapshot.executed;
+        self.outputs = snapshot.outputs;
+        self.clear_history();
+        self
+    }
+
+    // Returns the value of memory at the given position, `pos`.
+    pub fn peek_mem(&self, pos: usize) -> W {
+        self.mem.get(pos)
     }
 
     pub fn reason_for_stop(&self) -> Option<Stop> {
         self.reason
     }
+
+    /// Returns the current value of the instruction pointer.
+    pub fn ip(&self) -> usize {
+        self.ip
+    }
+
+    /// Returns the current relative base.
+    pub fn rel_base(&self) -> W {
+        self.rel_base.clone()
+    }
+
+    /// Sets whether arithmetic overflow stops the program with
+    /// `IntcodeError::Overflow`, rather than wrapping around, which is the
+    /// default.
+    pub fn set_checked_arithmetic(&mut self, checked: bool) -> &mut Self {
+        self.checked = checked;
+        self
+    }
+
+    /// Sets whether the program executes the instructions it decoded when it
+    /// was loaded, which is the default, or decodes every instruction as it
+    /// is executed.
+    ///
+    /// Either way, the program behaves the same; this is only useful for
+    /// measuring how much faster predecoding is.
+    pub fn set_decode_cache(&mut self, enabled: bool) -> &mut Self {
+        self.decoded.set_enabled(enabled);
+        self
+    }
+
+    /// Limits the program to addresses below `limit`, or lifts the limit if
+    /// `limit` is `None`.
+    ///
+    /// An instruction that refers to any address at or above the limit is
+    /// not executed, and the program stops with `Stop::MemoryLimit`
+    /// instead, so a runaway address cannot make the program allocate more
+    /// memory than that.
+    pub fn set_memory_limit(&mut self, limit: Option<usize>) -> &mut Self {
+        self.memory_limit = limit;
+        self
+    }
+
+    /// Limits the number of instructions the program may execute, or lifts
+    /// the limit if `limit` is `None`.
+    ///
+    /// Once the program has executed `limit` instructions, it stops with
+    /// `Stop::InstructionLimit`.
+    pub fn set_instruction_limit(&mut self, limit: Option<u64>) -> &mut Self {
+        self.instruction_limit = limit;
+        self
+    }
+
+    /// Limits the number of values the program may output, or lifts the
+    /// limit if `limit` is `None`.
+    ///
+    /// Once the program has output `limit` values, it stops with
+    /// `Stop::OutputLimit` at its next output instruction.
+    pub fn set_output_limit(&mut self, limit: Option<u64>) -> &mut Self {
+        self.output_limit = limit;
+        self
+    }
+
+    /// Returns the number of instructions the program has executed since it
+    /// was loaded, or last reset.
+    pub fn instruction_count(&self) -> u64 {
+        self.executed
+    }
+
+    /// Returns the number of values the program has output since it was
+    /// loaded, or last reset.
+    pub fn output_count(&self) -> u64 {
+        self.outputs
+    }
+
+    /// Returns the opcodes the program understands.
+    pub fn dialect(&self) -> &Dialect<W> {
+        &self.dialect
+    }
+
+    /// Sets the opcodes the program understands to `dialect`.
+    pub fn set_dialect(&mut self, dialect: Dialect<W>) -> &mut Self {
+        let stale = self.decoded.stale().clone();
+        self.dialect = Arc::new(dialect);
+        self.decoded.reload(&self.initial, &self.dialect);
+        self.decoded.set_stale(&stale);
+        self
+    }
+
+    /// Sets a breakpoint on the instruction at `address`.
+    pub fn add_breakpoint(&mut self, address: usize) -> &mut Self {
+        self.breakpoints.insert(address);
+        self
+    }
+
+    /// Removes the breakpoint at `address`, returning whether there was one.
+    pub fn remove_breakpoint(&mut self, address: usize) -> bool {
+        self.breakpoints.remove(&address)
+    }
+
+    /// Removes all breakpoints.
+    pub fn clear_breakpoints(&mut self) -> &mut Self {
+        self.breakpoints.clear();
+        self
+    }
+
+    /// Sets a watchpoint on the memory at `address`.
+    /// Only writes made by the program itself are watched; writes made
+    /// through `set_mem` are not.
+    pub fn add_watchpoint(&mut self, address: usize) -> &mut Self {
+        self.watchpoints.insert(address);
+        self
+    }
+
+    /// Removes the watchpoint at `address`, returning whether there was one.
+    pub fn remove_watchpoint(&mut self, address: usize) -> bool {
+        self.watchpoints.remove(&address)
+    }
+
+    /// Removes all watchpoints.
+    pub fn clear_watchpoints(&mut self) -> &mut Self {
+        self.watchpoints.clear();
+        self
+    }
+}
+
+/// Snapshot holds the state of a `Program` at a point in time: its memory,
+/// instruction pointer, relative base, pending input, the reason it last
+/// stopped, and how many instructions and outputs count against its limits.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Snapshot<W: Word = i64> {
+    mem: Memory<W>,
+    input: VecDeque<W>,
+    rel_base: W,
+    ip: usize,
+    reason: Option<Stop>,
+    stale: Stale,
+    executed: u64,
+    outputs: u64,
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum Stop {
     WaitingForInput,
     HCF,
+
+    /// The next instruction refers to an address beyond the program's
+    /// memory limit.
+    MemoryLimit,
+
+    /// The program has executed as many instructions as its limit allows.
+    InstructionLimit,
+
+    /// The program has output as many values as its limit allows, and the
+    /// next instruction would output another.
+    OutputLimit,
+}
+
+/// Step describes what happened when `Program::step` executed an instruction.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Step<W = i64> {
+    /// The instruction was executed, and produced no output.
+    Executed,
+
+    /// The instruction produced an output value.
+    Output(W),
+
+    /// The instruction wrote to a watched memory address.
+    Watchpoint(Watch<W>),
+
+    /// The program stopped, and the instruction pointer was not advanced.
+    Stopped(Stop),
+}
+
+/// Pause is the reason `Program::run_until` returned.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Pause<W = i64> {
+    /// The predicate passed to `run_until` returned `true`.
+    Predicate,
+
+    /// The instruction pointer reached a breakpoint.
+    /// The instruction at the breakpoint has not been executed yet.
+    Breakpoint(usize),
+
+    /// An instruction wrote to a watched memory address.
+    Watchpoint(Watch<W>),
+
+    /// The program stopped.
+    Stopped(Stop),
+}
+
+/// Watch holds the details of a write to a watched memory address.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Watch<W = i64> {
+    /// The address of the instruction that made the write.
+    pub ip: usize,
+
+    /// The memory address that was written to.
+    pub address: usize,
+
+    /// The value held at `address` before the write.
+    pub old: W,
+
+    /// The value written to `address`.
+    pub new: W,
+}
+
+/// IntcodeError is returned when a `Program` runs into an instruction it
+/// cannot execute.
+///
+/// Every variant carries the instruction pointer (`ip`) of the faulting
+/// instruction, and the raw instruction `word` found at that address.
+/// Values that do not fit in an `i64` are saturated.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum IntcodeError {
+    /// The opcode in `word` is not a known instruction.
+    UnknownOpcode { ip: usize, word: i64 },
+
+    /// One of the parameter modes in `word` is not 0, 1, or 2.
+    InvalidMode { ip: usize, word: i64, mode: i64 },
+
+    /// The instruction tried to write to an immediate-mode parameter.
+    ImmediateWrite { ip: usize, word: i64 },
+
+    /// A parameter, jump target, or relative address resolved to a negative
+    /// memory address.
+    NegativeAddress { ip: usize, word: i64, address: i64 },
+
+    /// A parameter, jump target, or relative address resolved to a memory
+    /// address too large to be used.
+    AddressOverflow { ip: usize, word: i64 },
+
+    /// The instruction's parameters extend past the end of memory.
+    Truncated { ip: usize, word: i64 },
+
+    /// An addition or multiplication overflowed, while the program was using
+    /// checked arithmetic.
+    Overflow { ip: usize, word: i64 },
+}
+
+impl IntcodeError {
+    /// Returns the address of the faulting instruction.
+    pub fn ip(&self) -> usize {
+        match *self {
+            Self::UnknownOpcode { ip, .. } => ip,
+            Self::InvalidMode { ip, .. } => ip,
+            Self::ImmediateWrite { ip, .. } => ip,
+            Self::NegativeAddress { ip, .. } => ip,
+            Self::AddressOverflow { ip, .. } => ip,
+            Self::Truncated { ip, .. } => ip,
+            Self::Overflow { ip, .. } => ip,
+        }
+    }
+
+    /// Returns the raw instruction word of the faulting instruction.
+    pub fn word(&self) -> i64 {
+        match *self {
+            Self::UnknownOpcode { word, .. } => word,
+            Self::InvalidMode { word, .. } => word,
+            Self::ImmediateWrite { word, .. } => word,
+            Self::NegativeAddress { word, .. } => word,
+            Self::AddressOverflow { word, .. } => word,
+            Self::Truncated { word, .. } => word,
+            Self::Overflow { word, .. } => word,
+        }
+    }
+}
+
+impl fmt::Display for IntcodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownOpcode { ip, word } => {
+                write!(f, "{:04}: unknown opcode in {}", ip, word)
+            }
+            Self::InvalidMode { ip, word, mode } => {
+                write!(f, "{:04}: invalid parameter mode {} in {}", ip, mode, word)
+            }
+            Self::ImmediateWrite { ip, word } => {
+                write!(f, "{:04}: write to immediate parameter in {}", ip, word)
+            }
+            Self::NegativeAddress { ip, word, address } => {
+                write!(f, "{:04}: negative address {} in {}", ip, address, word)
+            }
+            Self::AddressOverflow { ip, word } => {
+                write!(f, "{:04}: address out of range in {}", ip, word)
+            }
+            Self::Truncated { ip, word } => {
+                write!(f, "{:04}: truncated instruction {}", ip, word)
+            }
+            Self::Overflow { ip, word } => {
+                write!(f, "{:04}: arithmetic overflow in {}", ip, word)
+            }
+        }
+    }
+}
+
+impl error::Error for IntcodeError {}
+
+impl From<IntcodeError> for io::Error {
+    fn from(e: IntcodeError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, e)
+    }
 }
 
-#[derive(Debug)]
-enum Instruction {
-    Add(Parameter, Parameter, Parameter),
-    Multiply(Parameter, Parameter, Parameter),
-    Input(Parameter),
-    Output(Parameter),
-    JumpIfTrue(Parameter, Parameter),
-    JumpIfFalse(Parameter, Parameter),
-    LessThan(Parameter, Parameter, Parameter),
-    Equals(Parameter, Parameter, Parameter),
-    RelativeBaseOffset(Parameter),
+// What to divide an instruction word by, to get the mode of each of its
+// parameters in the lowest digit.
+const MODE_DIVISORS: [i64; 3] = [100, 1_000, 10_000];
+
+#[derive(Clone, Debug)]
+enum Instruction<W> {
+    Add(Parameter<W>, Parameter<W>, Parameter<W>),
+    Multiply(Parameter<W>, Parameter<W>, Parameter<W>),
+    Input(Parameter<W>),
+    Output(Parameter<W>),
+    JumpIfTrue(Parameter<W>, Parameter<W>),
+    JumpIfFalse(Parameter<W>, Parameter<W>),
+    LessThan(Parameter<W>, Parameter<W>, Parameter<W>),
+    Equals(Parameter<W>, Parameter<W>, Parameter<W>),
+    RelativeBaseOffset(Parameter<W>),
 
     /// Program stop.
     HCF,
 
-    /// BadInput is returned by `Instruction::parse` when there are too many,
-    /// or too few parameters provided.
-    BadInput(Vec<isize>),
+    /// An opcode registered with a program's `Dialect`, and its parameters.
+    /// It is kept behind an `Arc`, so that the built-in instructions, which
+    /// are executed far more often, stay small and cheap to copy.
+    Custom(Arc<Custom<W>>),
 }
 
-impl Instruction {
-    /// Parse the given instruction an parameters in `v`.
+impl<W: Word> Instruction<W> {
+    /// Parse the given instruction an parameters in `v`, which were loaded
+    /// from address `ip`.
     /// The number of parameters for the instruction can be figured out by
     /// calling `Instruction::num_params` on the instruction.
     ///
@@ -397,81 +1096,119 @@ impl Instruction {
     /// In plain English, this multiply instruction would multiply the value
     /// at program position 4, with the immediate value 3, and
     /// store the result in position 4.
-    fn parse(v: Vec<isize>) -> Self {
-        let np = Self::num_params(v[0]);
+    fn parse(ip: usize, v: &[W], dialect: &Dialect<W>) -> Result<Self, IntcodeError> {
+        let word = v[0].saturating_i64();
+        let np = match v[0].to_i64().and_then(|word| dialect.num_params(word)) {
+            Some(np) => np,
+            None => {
+                return Err(IntcodeError::UnknownOpcode { ip, word });
+            }
+        };
         if np + 1 != v.len() {
-            return Self::BadInput(v);
+            return Err(IntcodeError::Truncated { ip, word });
         }
 
-        // Collect our parameters.
-        let mut params: Vec<Parameter> = vec![];
-        for i in 0..np {
-            // Figure out the "mode" for the current parameter we are consuming.
+        // Decode the parameter at `i`.
+        let p = |i: usize| {
+            // Figure out the "mode" for the parameter.
             // 0 => (default) Positional mode.
             // 1 => Immediate mode.
             // 2 => Relative mode.
-            let mode = (v[0] / 10_isize.pow(i as u32 + 2)) % 10;
-            let param = match mode {
-                0 => Parameter::Positional(v[i + 1] as usize),
-                1 => Parameter::Immediate(v[i + 1]),
-                2 => Parameter::Relative(v[i + 1]),
-                _ => Parameter::UnexpectedMode(mode),
-            };
-            params.push(param);
-        }
-
-        // Parse the final argument as an output pointer.
-        // Enough instructions use this to know which memory location to store
-        // their output, that it makes sense to parse it all the time.
-        //let optr: usize = v[v.len() - 1] as usize;
+            let mode = (word / MODE_DIVISORS[i]) % 10;
+            let v = &v[i + 1];
+            match mode {
+                0 if v.is_negative() => Err(IntcodeError::NegativeAddress {
+                    ip,
+                    word,
+                    address: v.saturating_i64(),
+                }),
+                0 => match v.to_usize().filter(|&address| address < MAX_MEMORY) {
+                    Some(address) => Ok(Parameter::Positional(address)),
+                    None => Err(IntcodeError::AddressOverflow { ip, word }),
+                },
+                1 => Ok(Parameter::Immediate(v.clone())),
+                2 => Ok(Parameter::Relative(v.clone())),
+                _ => Err(IntcodeError::InvalidMode { ip, word, mode }),
+            }
+        };
 
         // Finally, return an Instruction.
-        match Instruction::ones(v[0]) {
-            1 => Self::Add(params[0], params[1], params[2]),
-            2 => Self::Multiply(params[0], params[1], params[2]),
-            3 => Self::Input(params[0]),
-            4 => Self::Output(params[0]),
-            5 => Self::JumpIfTrue(params[0], params[1]),
-            6 => Self::JumpIfFalse(params[0], params[1]),
-            7 => Self::LessThan(params[0], params[1], params[2]),
-            8 => Self::Equals(params[0], params[1], params[2]),
-            9 => match Instruction::tens(v[0]) {
-                0 => Self::RelativeBaseOffset(params[0]),
-                9 => Self::HCF,
-                _ => Self::BadInput(v),
-            },
-            _ => Self::BadInput(v),
-        }
-    }
-
-    // Returns the number of parameters that should be consumed by the opcode.
-    fn num_params(opcode: isize) -> usize {
-        if opcode == 99 {
-            return 0;
-        }
-
-        match Instruction::ones(opcode) {
-            1 => 3, // addition
-            2 => 3, // multiplication
-            3 => 1, // input
-            4 => 1, // output
-            5 => 2, // jump-if-true
-            6 => 2, // jump-if-false
-            7 => 3, // less-than
-            8 => 3, // equals
-            9 => 1, // relative base offset
+        if let Some(op) = dialect.custom(word) {
+            let mut params = [None, None, None];
+            for (i, param) in params.iter_mut().enumerate().take(np) {
+                *param = Some(p(i)?);
+            }
+            return Ok(Self::Custom(Arc::new(Custom {
+                opcode: (word % 100) as u8,
+                op: op.clone(),
+                params,
+            })));
+        }
+        let inst = match word % 100 {
+            1 => Self::Add(p(0)?, p(1)?, p(2)?),
+            2 => Self::Multiply(p(0)?, p(1)?, p(2)?),
+            3 => Self::Input(p(0)?),
+            4 => Self::Output(p(0)?),
+            5 => Self::JumpIfTrue(p(0)?, p(1)?),
+            6 => Self::JumpIfFalse(p(0)?, p(1)?),
+            7 => Self::LessThan(p(0)?, p(1)?, p(2)?),
+            8 => Self::Equals(p(0)?, p(1)?, p(2)?),
+            9 => Self::RelativeBaseOffset(p(0)?),
+            99 => Self::HCF,
             _ => {
-                panic!("Bad opcode {}", opcode);
+                return Err(IntcodeError::UnknownOpcode { ip, word });
             }
+        };
+        Ok(inst)
+    }
+
+    // Returns the parameters the instruction reads from, and the parameter
+    // it writes to.
+    fn operands(&self) -> ([Option<&Parameter<W>>; 2], Option<&Parameter<W>>) {
+        match self {
+            Self::Add(a, b, c)
+            | Self::Multiply(a, b, c)
+            | Self::LessThan(a, b, c)
+            | Self::Equals(a, b, c) => ([Some(a), Some(b)], Some(c)),
+            Self::JumpIfTrue(a, b) | Self::JumpIfFalse(a, b) => ([Some(a), Some(b)], None),
+            Self::Output(a) | Self::RelativeBaseOffset(a) => ([Some(a), None], None),
+            Self::Input(a) => ([None, None], Some(a)),
+            Self::HCF | Self::Custom(..) => ([None, None], None),
         }
     }
 
-    fn ones(n: isize) -> isize {
-        n % 10
+    // Returns every parameter of the instruction.
+    fn params(&self) -> impl Iterator<Item = &Parameter<W>> {
+        let params: [Option<&Parameter<W>>; MAX_PARAMS] = match self {
+            Self::Custom(custom) => [0, 1, 2].map(|i| custom.params[i].as_ref()),
+            _ => {
+                let ([a, b], c) = self.operands();
+                [a, b, c]
+            }
+        };
+        IntoIterator::into_iter(params).flatten()
     }
 
-    fn tens(n: isize) -> isize {
-        (n / 10) % 10
+    // Returns the number of parameters that should be consumed by the opcode,
+    // or `None` if the opcode is not recognized.
+    fn num_params(opcode: i64) -> Option<usize> {
+        if opcode < 0 {
+            return None;
+        }
+
+        match opcode % 100 {
+            1 => Some(3),  // addition
+            2 => Some(3),  // multiplication
+            3 => Some(1),  // input
+            4 => Some(1),  // output
+            5 => Some(2),  // jump-if-true
+            6 => Some(2),  // jump-if-false
+            7 => Some(3),  // less-than
+            8 => Some(3),  // equals
+            9 => Some(1),  // relative base offset
+            99 => Some(0), // halt
+            _ => None,
+        }
     }
 }
 
@@ -485,27 +1222,32 @@ impl Instruction {
 /// By default, the relative base is 0 (zero).
 /// The relative base should be added to whatever the current relative base is.
 #[derive(Clone, Copy, Debug)]
-enum Parameter {
+enum Parameter<W> {
     Positional(usize),
-    Immediate(isize),
-    Relative(isize),
-
-    // Indicates the parameter has an unexpected mode.
-    UnexpectedMode(isize),
+    Immediate(W),
+    Relative(W),
 }
 
+// Day 5's larger example, which compares its input to 8.
+#[cfg(test)]
+const LARGE_EXAMPLE: &str = "3,21,1008,21,8,20,1005,20,22,107,8,21,20,1006,20,31,1106,0,36,98,0,0,1002,21,125,20,4,20,1105,1,46,104,999,1105,1,46,1101,1000,1,20,4,20,1105,1,46,98,99";
+
+// Day 9's example, which outputs a copy of itself.
+#[cfg(test)]
+const QUINE: &str = "109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99";
+
 #[test]
 fn test_num_params() {
-    assert_eq!(Instruction::num_params(1), 3);
-    assert_eq!(Instruction::num_params(2), 3);
-    assert_eq!(Instruction::num_params(3), 1);
-    assert_eq!(Instruction::num_params(4), 1);
-    assert_eq!(Instruction::num_params(5), 2);
-    assert_eq!(Instruction::num_params(6), 2);
-    assert_eq!(Instruction::num_params(7), 3);
-    assert_eq!(Instruction::num_params(8), 3);
-    assert_eq!(Instruction::num_params(9), 1);
-    assert_eq!(Instruction::num_params(99), 0);
+    assert_eq!(Instruction::<i64>::num_params(1), Some(3));
+    assert_eq!(Instruction::<i64>::num_params(2), Some(3));
+    assert_eq!(Instruction::<i64>::num_params(3), Some(1));
+    assert_eq!(Instruction::<i64>::num_params(4), Some(1));
+    assert_eq!(Instruction::<i64>::num_params(5), Some(2));
+    assert_eq!(Instruction::<i64>::num_params(6), Some(2));
+    assert_eq!(Instruction::<i64>::num_params(7), Some(3));
+    assert_eq!(Instruction::<i64>::num_params(8), Some(3));
+    assert_eq!(Instruction::<i64>::num_params(9), Some(1));
+    assert_eq!(Instruction::<i64>::num_params(99), Some(0));
 }
 
 #[test]
@@ -606,7 +1348,7 @@ fn test_large_example() {
     // * 1000 if the input is equal to 8;
     // * 1001 if the input is greater-than 8.
     //
-    let code = String::from("3,21,1008,21,8,20,1005,20,22,107,8,21,20,1006,20,31,1106,0,36,98,0,0,1002,21,125,20,4,20,1105,1,46,104,999,1105,1,46,1101,1000,1,20,4,20,1105,1,46,98,99");
+    let code = String::from(LARGE_EXAMPLE);
     let mut program = Program::from(code.as_str());
 
     let output = program.input(7).execute().unwrap();
@@ -625,7 +1367,7 @@ fn test_large_example() {
 #[test]
 fn test_boost_self() {
     // The output should be the initial program.
-    let code = String::from("109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99");
+    let code = String::from(QUINE);
     let mut program = Program::from(code.as_str());
 
     let out = program.execute().unwrap();
@@ -653,3 +1395,392 @@ fn test_boost_middle_num() {
     assert_eq!(out.len(), 1);
     assert_eq!(out[0], 1125899906842624);
 }
+
+#[test]
+fn test_unknown_opcode() {
+    let mut program = Program::from("1101,1,2,5,42,99");
+    let err = program.execute().unwrap_err();
+    assert_eq!(err, IntcodeError::UnknownOpcode { ip: 4, word: 42 });
+    assert_eq!(program.peek_mem(5), 3);
+}
+
+#[test]
+fn test_invalid_mode() {
+    let mut program = Program::from("1301,1,2,5,99");
+    let err = program.execute().unwrap_err();
+    assert_eq!(
+        err,
+        IntcodeError::InvalidMode {
+            ip: 0,
+            word: 1301,
+            mode: 3
+        }
+    );
+}
+
+#[test]
+fn test_immediate_write() {
+    let mut program = Program::from("11101,1,2,5,99");
+    let err = program.execute().unwrap_err();
+    assert_eq!(err, IntcodeError::ImmediateWrite { ip: 0, word: 11101 });
+}
+
+#[test]
+fn test_negative_address() {
+    // Positional parameter.
+    let mut program = Program::from("4,-1,99");
+    let err = program.execute().unwrap_err();
+    assert_eq!(
+        err,
+        IntcodeError::NegativeAddress {
+            ip: 0,
+            word: 4,
+            address: -1
+        }
+    );
+
+    // Relative parameter, after moving the relative base.
+    let mut program = Program::from("109,-5,204,2,99");
+    let err = program.execute().unwrap_err();
+    assert_eq!(
+        err,
+        IntcodeError::NegativeAddress {
+            ip: 2,
+            word: 204,
+            address: -3
+        }
+    );
+
+    // Jump target.
+    let mut program = Program::from("1105,1,-7");
+    let err = program.execute().unwrap_err();
+    assert_eq!(
+        err,
+        IntcodeError::NegativeAddress {
+            ip: 0,
+            word: 1105,
+            address: -7
+        }
+    );
+}
+
+#[test]
+fn test_truncated() {
+    let mut program = Program::from("1,0,0");
+    let err = program.execute().unwrap_err();
+    assert_eq!(err, IntcodeError::Truncated { ip: 0, word: 1 });
+}
+
+#[test]
+fn test_run_off_end() {
+    // There is no halt instruction, so execution runs into zeroed memory.
+    let mut program = Program::from("1101,1,2,5");
+    let err = program.execute().unwrap_err();
+    assert_eq!(err, IntcodeError::UnknownOpcode { ip: 4, word: 0 });
+}
+
+#[test]
+fn test_self_modifying() {
+    // The first instruction overwrites the halt at address 4 with a
+    // multiplication, so the instruction decoded there when the program was
+    // loaded must not be used.
+    let mut program = Program::from("1,1,1,4,99,5,6,0,99");
+    assert_eq!(program.execute().unwrap(), vec![]);
+    assert_eq!(program.peek_mem(0), 30);
+
+    // Once reset, the halt is back, and redirecting the first instruction's
+    // output leaves it alone.
+    program.reset_to_initial().set_mem(3, 9);
+    program.execute().unwrap();
+    assert_eq!(program.ip(), 4);
+    assert_eq!(program.peek_mem(9), 2);
+
+    let mut program = Program::from("1,1,1,4,99,5,6,0,99");
+    program.set_decode_cache(false);
+    program.execute().unwrap();
+    assert_eq!(program.peek_mem(0), 30);
+}
+
+#[test]
+fn test_step() {
+    let mut program = Program::from("3,9,1001,9,5,9,4,9,99,0");
+    assert_eq!(
+        program.step().unwrap(),
+        Step::Stopped(Stop::WaitingForInput)
+    );
+    assert_eq!(program.ip(), 0);
+
+    program.input(10);
+    assert_eq!(program.step().unwrap(), Step::Executed);
+    assert_eq!(program.ip(), 2);
+    assert_eq!(program.step().unwrap(), Step::Executed);
+    assert_eq!(program.ip(), 6);
+    assert_eq!(program.step().unwrap(), Step::Output(15));
+    assert_eq!(program.step().unwrap(), Step::Stopped(Stop::HCF));
+    assert_eq!(program.step().unwrap(), Step::Stopped(Stop::HCF));
+    assert_eq!(program.ip(), 8);
+}
+
+#[test]
+fn test_rel_base() {
+    let mut program = Program::from("109,19,109,-4,99");
+    program.execute().unwrap();
+    assert_eq!(program.rel_base(), 15);
+}
+
+#[test]
+fn test_run_until_breakpoint() {
+    let code = "109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99";
+    let mut program = Program::from(code);
+    program.add_breakpoint(15);
+
+    let (pause, out) = program.run_until(|_| false).unwrap();
+    assert_eq!(pause, Pause::Breakpoint(15));
+    assert_eq!(out.len(), 16);
+    assert_eq!(program.peek_mem(100), 16);
+
+    // Continuing runs past the breakpoint.
+    let (pause, out) = program.run_until(|_| false).unwrap();
+    assert_eq!(pause, Pause::Stopped(Stop::HCF));
+    assert_eq!(out.len(), 0);
+}
+
+#[test]
+fn test_run_until_watchpoint() {
+    let code = "109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99";
+    let mut program = Program::from(code);
+    program.add_watchpoint(100);
+
+    let (pause, out) = program.run_until(|_| false).unwrap();
+    assert_eq!(
+        pause,
+        Pause::Watchpoint(Watch {
+            ip: 4,
+            address: 100,
+            old: 0,
+            new: 1
+        })
+    );
+    assert_eq!(out, vec![109]);
+    assert_eq!(program.ip(), 8);
+
+    assert!(program.remove_watchpoint(100));
+    let (pause, out) = program.run_until(|_| false).unwrap();
+    assert_eq!(pause, Pause::Stopped(Stop::HCF));
+    assert_eq!(out.len(), 15);
+}
+
+#[test]
+fn test_run_until_predicate() {
+    let code = "109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99";
+    let mut program = Program::from(code);
+    let (pause, out) = program.run_until(|p| p.peek_mem(100) == 3).unwrap();
+    assert_eq!(pause, Pause::Predicate);
+    assert_eq!(out, vec![109, 1, 204]);
+    assert_eq!(program.ip(), 8);
+}
+
+#[test]
+fn test_clone() {
+    let code = "3,21,1008,21,8,20,1005,20,22,107,8,21,20,1006,20,31,1106,0,36,98,0,0,1002,21,125,20,4,20,1105,1,46,104,999,1105,1,46,1101,1000,1,20,4,20,1105,1,46,98,99";
+    let mut program = Program::from(code);
+    assert_eq!(program.execute().unwrap().len(), 0);
+
+    // Branch off from the point where the program waits for input.
+    let mut a = program.clone();
+    let mut b = program.clone();
+    assert_eq!(a.input(7).execute().unwrap(), vec![999]);
+    assert_eq!(b.input(9).execute().unwrap(), vec![1001]);
+    assert_eq!(program.input(8).execute().unwrap(), vec![1000]);
+}
+
+#[test]
+fn test_snapshot_restore() {
+    let code = "109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99";
+    let mut program = Program::from(code);
+    program.input(42);
+    program.add_breakpoint(4);
+    program.run_until(|_| false).unwrap();
+    let snapshot = program.snapshot();
+
+    let out = program.execute().unwrap();
+    assert_eq!(out.len(), 15);
+    assert_eq!(program.reason_for_stop(), Some(Stop::HCF));
+    assert_eq!(program.peek_mem(100), 16);
+
+    program.restore(&snapshot);
+    assert_eq!(program.ip(), 4);
+    assert_eq!(program.rel_base(), 1);
+    assert_eq!(program.reason_for_stop(), None);
+    assert_eq!(program.peek_mem(100), 0);
+    assert_eq!(program.snapshot(), snapshot);
+    assert_eq!(program.execute().unwrap(), out);
+}
+
+#[test]
+fn test_reset_to_initial() {
+    let code = "3,9,8,9,10,9,4,9,99,-1,8";
+    let mut program = Program::from(code);
+    assert_eq!(program.input(8).execute().unwrap(), vec![1]);
+    assert_eq!(program.peek_mem(9), 1);
+
+    program.input(5).reset_to_initial();
+    assert_eq!(program.peek_mem(9), -1);
+    assert_eq!(program.reason_for_stop(), None);
+    assert_eq!(program.execute().unwrap().len(), 0);
+    assert_eq!(program.reason_for_stop(), Some(Stop::WaitingForInput));
+    assert_eq!(program.input(88).execute().unwrap(), vec![0]);
+}
+
+#[test]
+fn test_fork() {
+    let code = "3,21,1008,21,8,20,1005,20,22,107,8,21,20,1006,20,31,1106,0,36,98,0,0,1002,21,125,20,4,20,1105,1,46,104,999,1105,1,46,1101,1000,1,20,4,20,1105,1,46,98,99";
+    let mut program = Program::from(code);
+    assert_eq!(program.backend(), Backend::Flat);
+    assert_eq!(program.execute().unwrap().len(), 0);
+
+    let mut fork = program.fork();
+    assert_eq!(program.backend(), Backend::Paged);
+    assert_eq!(fork.input(7).execute().unwrap(), vec![999]);
+    assert_eq!(program.input(9).execute().unwrap(), vec![1001]);
+    assert_eq!(fork.peek_mem(21), 7);
+    assert_eq!(program.peek_mem(21), 9);
+
+    // Paged memory still grows, and reads as zero past the end.
+    fork.set_mem(5000, 1);
+    assert_eq!(fork.peek_mem(5000), 1);
+    assert_eq!(fork.peek_mem(4999), 0);
+    assert_eq!(program.peek_mem(5000), 0);
+
+    // Switching back to flat memory keeps the program's state.
+    let snapshot = fork.snapshot();
+    fork.set_backend(Backend::Flat);
+    assert_eq!(fork.backend(), Backend::Flat);
+    assert_eq!(fork.snapshot(), snapshot);
+    fork.reset_to_initial();
+    assert_eq!(fork.input(8).execute().unwrap(), vec![1000]);
+}
+
+#[test]
+fn test_limits() {
+    // Reading and writing far past the end of memory.
+    let mut program = Program::from("109,1000000,204,0,99");
+    program.set_memory_limit(Some(1024));
+    assert_eq!(program.execute().unwrap(), vec![]);
+    assert_eq!(program.reason_for_stop(), Some(Stop::MemoryLimit));
+    assert_eq!(program.ip(), 2);
+
+    let mut program = Program::from("1101,1,1,5000,99");
+    program.set_memory_limit(Some(1024));
+    program.execute().unwrap();
+    assert_eq!(program.reason_for_stop(), Some(Stop::MemoryLimit));
+    assert_eq!(program.peek_mem(5000), 0);
+    program.set_memory_limit(None).execute().unwrap();
+    assert_eq!(program.reason_for_stop(), Some(Stop::HCF));
+    assert_eq!(program.peek_mem(5000), 2);
+
+    // An infinite loop.
+    let mut program = Program::from("1105,1,0");
+    program.set_instruction_limit(Some(10));
+    program.execute().unwrap();
+    assert_eq!(program.reason_for_stop(), Some(Stop::InstructionLimit));
+    assert_eq!(program.instruction_count(), 10);
+    program.set_instruction_limit(Some(25)).execute().unwrap();
+    assert_eq!(program.instruction_count(), 25);
+    program.reset_to_initial();
+    assert_eq!(program.instruction_count(), 0);
+
+    // An infinite loop that outputs forever.
+    let mut program = Program::from("104,1,1105,1,0");
+    program.set_output_limit(Some(3));
+    assert_eq!(program.execute().unwrap(), vec![1, 1, 1]);
+    assert_eq!(program.reason_for_stop(), Some(Stop::OutputLimit));
+    assert_eq!((program.ip(), program.output_count()), (0, 3));
+}
+
+#[test]
+fn test_overflow() {
+    // Multiply 2^62 by 4, which does not fit in an i64.
+    let code = "1102,4611686018427387904,4,7,4,7,99,0";
+    let mut program = Program::from(code);
+    assert_eq!(program.execute().unwrap(), vec![0]);
+
+    let mut program = Program::from(code);
+    program.set_checked_arithmetic(true);
+    let err = program.execute().unwrap_err();
+    assert_eq!(err, IntcodeError::Overflow { ip: 0, word: 1102 });
+    assert_eq!(program.ip(), 0);
+    assert_eq!(program.peek_mem(7), 0);
+
+    // Moving the relative base is checked, too.
+    let mut program = Program::from("109,9223372036854775807,109,1,99");
+    program.set_checked_arithmetic(true);
+    let err = program.execute().unwrap_err();
+    assert_eq!(err, IntcodeError::Overflow { ip: 2, word: 109 });
+
+    // Relative addresses past the end of the relative base are an error,
+    // whether or not the program uses checked arithmetic, for reading and
+    // writing alike.
+    let mut program = Program::from("109,9223372036854775807,1201,1,0,0,99");
+    let err = program.execute().unwrap_err();
+    assert_eq!(err, IntcodeError::Overflow { ip: 2, word: 1201 });
+    let mut program = Program::from("109,9223372036854775807,21101,1,1,1,99");
+    let err = program.execute().unwrap_err();
+    assert_eq!(err, IntcodeError::Overflow { ip: 2, word: 21101 });
+
+    // Without checked arithmetic, nothing else overflows with a panic.
+    let code = "109,9223372036854775807,109,1,1101,9223372036854775807,1,11,4,11,99,0";
+    let mut program = Program::from(code);
+    assert_eq!(program.execute().unwrap(), vec![i64::MIN]);
+    assert_eq!(program.rel_base(), i64::MIN);
+}
+
+#[test]
+fn test_word_sizes() {
+    use num::BigInt;
+
+    let code = "1102,4611686018427387904,4,7,4,7,99,0";
+    let mut program: Program<i128> = code.parse().unwrap();
+    program.set_checked_arithmetic(true);
+    assert_eq!(program.execute().unwrap(), vec![1 << 64]);
+
+    // Multiply 2^126 by 4, which does not fit in an i128 either, but does
+    // fit in a BigInt.
+    let code = "1102,85070591730234615865843651857942052864,4,7,4,7,99,0";
+    let mut program: Program<i128> = code.parse().unwrap();
+    program.set_checked_arithmetic(true);
+    let err = program.execute().unwrap_err();
+    assert_eq!(err, IntcodeError::Overflow { ip: 0, word: 1102 });
+
+    let mut program: Program<BigInt> = code.parse().unwrap();
+    program.set_checked_arithmetic(true);
+    assert_eq!(program.execute().unwrap(), vec![BigInt::from(1) << 128]);
+
+    // Addresses that are too large to use are an error, rather than being
+    // truncated.
+    let mut program: Program<BigInt> = "4,18446744073709551616,99".parse().unwrap();
+    let err = program.execute().unwrap_err();
+    assert_eq!(err, IntcodeError::AddressOverflow { ip: 0, word: 4 });
+
+    // So are addresses past `MAX_MEMORY`, rather than growing memory to
+    // reach them.
+    let mut program = Program::from("1101,1,1,100000000,99");
+    let err = program.execute().unwrap_err();
+    assert_eq!(err, IntcodeError::AddressOverflow { ip: 0, word: 1101 });
+    let mut program = Program::from("109,100000000,21101,1,1,0,99");
+    let err = program.execute().unwrap_err();
+    assert_eq!(err, IntcodeError::AddressOverflow { ip: 2, word: 21101 });
+    assert!(program.mem.len() < MAX_MEMORY);
+}
+
+#[test]
+fn test_parse() {
+    let input = Input::from("1,0,0\n3, 99\n");
+    let program = Program::<i64>::parse(&input).unwrap();
+    assert_eq!(program.save(), Program::from("1,0,0,3,99").save());
+
+    let input = Input::from("1,0,0\n3, 99,x\n");
+    let err = Program::<i64>::parse(&input).err().unwrap();
+    assert_eq!((err.line, err.column), (2, Some(7)));
+    assert_eq!(err.text, "x");
+}
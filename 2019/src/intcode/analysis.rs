@@ -0,0 +1,551 @@
+//! Static analysis of intcode programs.
+//!
+//! Unlike the disassembler, which decodes memory from start to finish, the
+//! analyzer only decodes the instructions a program can reach, by following
+//! every path through the program from address 0.
+//! The reachable instructions are split into basic blocks, which are linked
+//! into a control-flow graph by the jumps between them.
+//!
+//! A jump's target is only followed if it is known without running the
+//! program: an immediate parameter, or a positional parameter pointing at
+//! memory that no reachable instruction writes to.
+//! Writes through relative parameters are assumed to go to the stack, rather
+//! than to memory holding jump targets.
+//!
+//! Intcode has no call or return instructions, so a function call is
+//! recognized by the way compiled intcode programs make them: by pushing a
+//! return address onto the stack, which is the address just after an
+//! unconditional jump to the function.
+//! Functions return with a jump to an address read off of the stack, which
+//! cannot be followed, so the analyzer follows the return address instead.
+use super::decode::{decode, Decoded};
+use super::disasm::{Kind, Line};
+use super::{Dialect, Instruction, Parameter, Program, Word};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+use std::ops::Range;
+
+impl<W: Word> Program<W> {
+    /// Returns an analysis of the program's current memory.
+    pub fn analyze(&self) -> Analysis<W> {
+        Analysis::with_dialect(&self.mem.as_slice(), &self.dialect)
+    }
+}
+
+/// Analysis is the control-flow graph of an intcode program, along with
+/// which parts of its memory hold code, and which hold data.
+pub struct Analysis<W = i64> {
+    blocks: BTreeMap<usize, Block<W>>,
+    regions: Vec<Region>,
+    code_writes: Vec<CodeWrite>,
+    functions: BTreeSet<usize>,
+}
+
+/// Block is a basic block: a run of instructions that is only ever entered
+/// at its first instruction, and only ever left after its last one.
+pub struct Block<W = i64> {
+    start: usize,
+    end: usize,
+    lines: Vec<Line<W>>,
+    edges: Vec<Edge>,
+    exit: Exit,
+}
+
+/// Edge links a block to a block that execution can continue at.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Edge {
+    /// Execution continues at the instruction after the block.
+    Next(usize),
+
+    /// The block ends with a jump to the address.
+    Jump(usize),
+
+    /// The block ends with a function call, which returns to the address.
+    Return(usize),
+}
+
+/// Exit describes how execution leaves a block.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Exit {
+    /// Execution continues along the block's edges.
+    Static,
+
+    /// The block ends with a halt instruction.
+    Halt,
+
+    /// The block ends with a jump to an address that is only known when the
+    /// program runs, such as a function returning.
+    Dynamic,
+
+    /// The block runs into memory that is not a valid instruction.
+    Invalid,
+}
+
+/// Region is a run of memory holding either code or data.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Region {
+    /// Memory holding reachable instructions.
+    Code(Range<usize>),
+
+    /// Memory that no reachable instruction starts in, such as variables,
+    /// lookup tables, and the stack.
+    Data(Range<usize>),
+}
+
+/// CodeWrite is an instruction that writes to memory holding code, and so
+/// modifies the program itself.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct CodeWrite {
+    /// The address of the instruction making the write.
+    pub ip: usize,
+
+    /// The address written to.
+    pub address: usize,
+}
+
+// How an instruction passes control to the next one.
+enum Flow {
+    Next,
+    Halt,
+    Jump {
+        // The address jumped to, if it is known.
+        target: Option<usize>,
+
+        // Whether the jump can be taken, and whether it can fall through to
+        // the next instruction, which depends on whether its condition is
+        // an immediate parameter.
+        taken: bool,
+        falls_through: bool,
+    },
+}
+
+// The reachable instructions of a program.
+struct Walk<W> {
+    insts: BTreeMap<usize, Decoded<W>>,
+
+    // Addresses that start a block.
+    leaders: BTreeSet<usize>,
+
+    // The address of each call instruction, and the address it returns to.
+    calls: BTreeMap<usize, usize>,
+    functions: BTreeSet<usize>,
+
+    // Every address written to by a positional parameter.
+    written: BTreeSet<usize>,
+}
+
+impl<W: Word> Analysis<W> {
+    /// Analyzes the program in `mem`, starting from address 0.
+    pub fn new(mem: &[W]) -> Self {
+        Self::with_dialect(mem, &Dialect::new())
+    }
+
+    // Analyzes the program in `mem`, whose opcodes are in `dialect`.
+    fn with_dialect(mem: &[W], dialect: &Dialect<W>) -> Self {
+        // Which jump targets are known depends on which memory is written
+        // to, which depends on which instructions are reachable, so walk the
+        // program until the memory written to stops growing.
+        let mut written = BTreeSet::new();
+        let walk = loop {
+            let walk = Walk::new(mem, dialect, &written);
+            if walk.written.is_subset(&written) {
+                break walk;
+            }
+            written.extend(walk.written);
+        };
+
+        let mut code = vec![false; mem.len()];
+        for (&ip, (_, next)) in &walk.insts {
+            code[ip..*next].iter_mut().for_each(|c| *c = true);
+        }
+
+        let code_writes = walk
+            .insts
+            .iter()
+            .filter_map(|(&ip, (inst, _))| {
+                let address = inst.write_address()?;
+                Some(CodeWrite { ip, address }).filter(|_| code.get(address) == Some(&true))
+            })
+            .collect();
+
+        let mut regions: Vec<Region> = vec![];
+        for (address, &is_code) in code.iter().enumerate() {
+            match regions.last_mut() {
+                Some(Region::Code(r)) if is_code => r.end += 1,
+                Some(Region::Data(r)) if !is_code => r.end += 1,
+                _ if is_code => regions.push(Region::Code(address..address + 1)),
+                _ => regions.push(Region::Data(address..address + 1)),
+            }
+        }
+
+        Self {
+            blocks: walk.blocks(mem, &written),
+            regions,
+            code_writes,
+            functions: walk.functions,
+        }
+    }
+
+    /// Returns each basic block, in order of address.
+    pub fn blocks(&self) -> impl Iterator<Item = &Block<W>> {
+        self.blocks.values()
+    }
+
+    /// Returns the basic block starting at `address`, if there is one.
+    pub fn block(&self, address: usize) -> Option<&Block<W>> {
+        self.blocks.get(&address)
+    }
+
+    /// Returns the program's memory, split into runs of code and data.
+    pub fn regions(&self) -> &[Region] {
+        &self.regions
+    }
+
+    /// Returns `true` if `address` is part of a reachable instruction.
+    pub fn is_code(&self, address: usize) -> bool {
+        self.regions.iter().any(|region| match region {
+            Region::Code(r) => r.contains(&address),
+            Region::Data(_) => false,
+        })
+    }
+
+    /// Returns every instruction that writes to memory holding code.
+    pub fn code_writes(&self) -> &[CodeWrite] {
+        &self.code_writes
+    }
+
+    /// Returns the address of each function the program calls.
+    pub fn functions(&self) -> impl Iterator<Item = usize> + '_ {
+        self.functions.iter().copied()
+    }
+
+    /// Renders the control-flow graph in Graphviz's DOT language, with each
+    /// block labelled with its disassembly.
+    ///
+    /// Function entry points are drawn with a double border, blocks that
+    /// modify the program's code are drawn in red, and the edge from each
+    /// function call to where it returns is dashed.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph intcode {\n");
+        dot.push_str("    node [shape=box, fontname=\"monospace\"];\n");
+        for block in self.blocks() {
+            let mut label = String::new();
+            for line in &block.lines {
+                write!(label, "{}\\l", line).unwrap();
+            }
+            match block.exit {
+                Exit::Dynamic => label.push_str("(dynamic jump)\\l"),
+                Exit::Invalid => label.push_str("(invalid instruction)\\l"),
+                Exit::Static | Exit::Halt => {}
+            }
+            let mut attrs = String::new();
+            if self.functions.contains(&block.start) {
+                attrs.push_str(", peripheries=2");
+            }
+            let range = block.start..block.end;
+            if self.code_writes.iter().any(|w| range.contains(&w.ip)) {
+                attrs.push_str(", color=red");
+            }
+            writeln!(dot, "    b{} [label=\"{}\"{}];", block.start, label, attrs).unwrap();
+
+            for edge in &block.edges {
+                let (to, attrs) = match *edge {
+                    Edge::Next(to) => (to, ""),
+                    Edge::Jump(to) => (to, " [label=\"jump\"]"),
+                    Edge::Return(to) => (to, " [style=dashed, label=\"return\"]"),
+                };
+                writeln!(dot, "    b{} -> b{}{};", block.start, to, attrs).unwrap();
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+impl<W> Block<W> {
+    /// Returns the address of the block's first instruction.
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    /// Returns the address just past the block's last instruction.
+    pub fn end(&self) -> usize {
+        self.end
+    }
+
+    /// Returns the disassembly of each of the block's instructions.
+    pub fn lines(&self) -> &[Line<W>] {
+        &self.lines
+    }
+
+    /// Returns where execution can continue after the block.
+    pub fn edges(&self) -> &[Edge] {
+        &self.edges
+    }
+
+    /// Returns how execution leaves the block.
+    pub fn exit(&self) -> Exit {
+        self.exit
+    }
+}
+
+impl<W: Word> Walk<W> {
+    // Finds every instruction reachable from address 0 in `mem`, assuming
+    // only the addresses in `written` are ever written to.
+    fn new(mem: &[W], dialect: &Dialect<W>, written: &BTreeSet<usize>) -> Self {
+        let mut walk = Self {
+            insts: BTreeMap::new(),
+            leaders: BTreeSet::new(),
+            calls: BTreeMap::new(),
+            functions: BTreeSet::new(),
+            written: BTreeSet::new(),
+        };
+        let mut work = vec![0];
+        walk.leaders.insert(0);
+        while let Some(start) = work.pop() {
+            // Follow the instructions from `start` in order, until the path
+            // ends, or joins a path that has already been followed.
+            let mut ip = start;
+            let mut pushed: Vec<usize> = vec![];
+            while !walk.insts.contains_key(&ip) {
+                let (inst, next) = match decode(mem, ip, dialect) {
+                    Some(decoded) => decoded,
+                    None => break,
+                };
+                walk.written.extend(inst.write_address());
+                pushed.extend(inst.pushed_constant());
+                let flow = inst.flow(mem, written);
+                walk.insts.insert(ip, (inst, next));
+
+                match flow {
+                    Flow::Next => {}
+                    Flow::Halt => break,
+                    Flow::Jump {
+                        target,
+                        taken,
+                        falls_through,
+                    } => {
+                        if let Some(target) = target.filter(|_| taken) {
+                            walk.leaders.insert(target);
+                            work.push(target);
+                            if !falls_through && pushed.contains(&next) {
+                                walk.calls.insert(ip, next);
+                                walk.functions.insert(target);
+                                walk.leaders.insert(next);
+                                work.push(next);
+                            }
+                        }
+                        if !falls_through {
+                            break;
+                        }
+                        walk.leaders.insert(next);
+                    }
+                }
+                ip = next;
+            }
+        }
+        walk
+    }
+
+    // Splits the reachable instructions into basic blocks.
+    fn blocks(&self, mem: &[W], written: &BTreeSet<usize>) -> BTreeMap<usize, Block<W>> {
+        let mut blocks = BTreeMap::new();
+        let mut block: Option<Block<W>> = None;
+        for (&ip, (inst, next)) in &self.insts {
+            let mut current = block.take().unwrap_or(Block {
+                start: ip,
+                end: ip,
+                lines: vec![],
+                edges: vec![],
+                exit: Exit::Static,
+            });
+            current.lines.push(Line {
+                address: ip,
+                kind: Kind::Instruction(inst.clone()),
+            });
+            current.end = *next;
+
+            let falls_into = self.insts.contains_key(next);
+            match inst.flow(mem, written) {
+                Flow::Next if falls_into && !self.leaders.contains(next) => {
+                    block = Some(current);
+                    continue;
+                }
+                Flow::Next if falls_into => current.edges.push(Edge::Next(*next)),
+                Flow::Next => current.exit = Exit::Invalid,
+                Flow::Halt => current.exit = Exit::Halt,
+                Flow::Jump {
+                    target,
+                    taken,
+                    falls_through,
+                } => {
+                    if taken {
+                        match target {
+                            Some(target) => current.edges.push(Edge::Jump(target)),
+                            None => current.exit = Exit::Dynamic,
+                        }
+                    }
+                    if falls_through {
+                        current.edges.push(Edge::Next(*next));
+                    }
+                    if let Some(&ret) = self.calls.get(&ip) {
+                        current.edges.push(Edge::Return(ret));
+                    }
+                }
+            }
+            blocks.insert(current.start, current);
+        }
+        blocks
+    }
+}
+
+impl<W: Word> Instruction<W> {
+    // Returns how the instruction passes control on, given the program's
+    // memory, and which addresses in it are written to.
+    fn flow(&self, mem: &[W], written: &BTreeSet<usize>) -> Flow {
+        let (cond, target, jump_if) = match self {
+            Self::JumpIfTrue(cond, target) => (cond, target, true),
+            Self::JumpIfFalse(cond, target) => (cond, target, false),
+            Self::HCF => return Flow::Halt,
+            _ => return Flow::Next,
+        };
+        let always = match cond {
+            Parameter::Immediate(v) => Some(v.is_zero() != jump_if),
+            _ => None,
+        };
+        let target = match target {
+            Parameter::Immediate(v) => v.to_usize(),
+            Parameter::Positional(p) if !written.contains(p) => {
+                mem.get(*p).and_then(|v| v.to_usize())
+            }
+            _ => None,
+        };
+        Flow::Jump {
+            target,
+            taken: always != Some(false),
+            falls_through: always != Some(true),
+        }
+    }
+
+    // Returns the address the instruction writes to, if it writes through a
+    // positional parameter.
+    fn write_address(&self) -> Option<usize> {
+        match self.operands().1? {
+            Parameter::Positional(address) => Some(*address),
+            _ => None,
+        }
+    }
+
+    // Returns the constant the instruction pushes onto the stack, if it
+    // writes the sum or product of two immediate parameters through a
+    // relative parameter, like a function call pushing its return address.
+    fn pushed_constant(&self) -> Option<usize> {
+        use Parameter::{Immediate, Relative};
+        match self {
+            Self::Add(Immediate(a), Immediate(b), Relative(_)) => a.checked_add(b)?.to_usize(),
+            Self::Multiply(Immediate(a), Immediate(b), Relative(_)) => a.checked_mul(b)?.to_usize(),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+use super::assemble;
+
+#[cfg(test)]
+fn summary(analysis: &Analysis) -> Vec<(usize, usize, Vec<Edge>, Exit)> {
+    analysis
+        .blocks()
+        .map(|b| (b.start(), b.end(), b.edges().to_vec(), b.exit()))
+        .collect()
+}
+
+#[test]
+fn test_loop() {
+    let src = "
+                in -> [n]
+        loop:   add [acc], [n] -> [acc]
+                add [n], #-1 -> [n]
+                jt [n], #loop
+                out [acc]
+                hlt
+        n:      data 0
+        acc:    data 0
+    ";
+    let analysis = Program::from(assemble(src).unwrap()).analyze();
+    assert_eq!(
+        summary(&analysis),
+        vec![
+            (0, 2, vec![Edge::Next(2)], Exit::Static),
+            (2, 13, vec![Edge::Jump(2), Edge::Next(13)], Exit::Static),
+            (13, 16, vec![], Exit::Halt),
+        ]
+    );
+    assert_eq!(
+        analysis.regions(),
+        &[Region::Code(0..16), Region::Data(16..18)]
+    );
+    assert!(analysis.code_writes().is_empty());
+
+    let dot = analysis.to_dot();
+    assert!(dot.starts_with("digraph intcode {\n"));
+    assert!(dot.contains("    b0 [label=\"0000: IN -> [16]\\l\"];\n"));
+    assert!(dot.contains("    b2 -> b2 [label=\"jump\"];\n    b2 -> b13;\n"));
+}
+
+#[test]
+fn test_functions() {
+    let src = "
+                arb #stack
+                in -> [rb+1]
+                add #back, #0 -> [rb+0]
+                jt #1, #double
+        back:   out [rb+1]
+                hlt
+        double: add [rb+1], [rb+1] -> [rb+1]
+                jt #1, [rb+0]
+        table:  data 1, 2, 3
+        stack:  data 0, 0
+    ";
+    let analysis = Program::from(assemble(src).unwrap()).analyze();
+    assert_eq!(
+        summary(&analysis),
+        vec![
+            (0, 11, vec![Edge::Jump(14), Edge::Return(11)], Exit::Static),
+            (11, 14, vec![], Exit::Halt),
+            (14, 21, vec![], Exit::Dynamic),
+        ]
+    );
+    assert_eq!(analysis.functions().collect::<Vec<_>>(), vec![14]);
+    assert_eq!(
+        analysis.regions(),
+        &[Region::Code(0..21), Region::Data(21..26)]
+    );
+    assert!(analysis.to_dot().contains("    b14 [label=\"0014: ADD [rb+1], [rb+1] -> [rb+1]\\l0018: JT #1, [rb+0]\\l(dynamic jump)\\l\", peripheries=2];\n"));
+
+    // A jump through a positional parameter is followed, unless something
+    // writes to it.
+    let analysis = Program::from("105,1,5,99,99,3").analyze();
+    assert_eq!(
+        summary(&analysis)[0],
+        (0, 3, vec![Edge::Jump(3)], Exit::Static)
+    );
+    let analysis = Program::from("1101,3,0,8,105,1,8,99,3").analyze();
+    assert_eq!(summary(&analysis)[0], (0, 7, vec![], Exit::Dynamic));
+}
+
+#[test]
+fn test_code_writes() {
+    // Day 2's example patches its own instructions.
+    let analysis = Program::from("1,9,10,3,2,3,11,0,99,30,40,50").analyze();
+    assert_eq!(
+        analysis.code_writes(),
+        &[
+            CodeWrite { ip: 0, address: 3 },
+            CodeWrite { ip: 4, address: 0 },
+        ]
+    );
+    assert!(analysis.to_dot().contains("\\l\", color=red];\n"));
+    assert!(analysis.is_code(8));
+    assert!(!analysis.is_code(9));
+}
@@ -0,0 +1,285 @@
+//! Talk to intcode programs that speak ASCII.
+//!
+//! Some intcode programs (like the vacuum robot in day 17, or the text
+//! adventure in day 25) read and write lines of ASCII text, one character
+//! per value.
+//! A `Terminal` wraps such a program, sending it lines of text, and turning
+//! its output back into text.
+//!
+//! Any output value that is not an ASCII character (usually the answer to the
+//! puzzle) is returned separately from the text.
+use super::{IntcodeError, Program, Step, Stop, Word};
+use std::error;
+use std::fmt;
+use std::io;
+
+/// Terminal wraps a `Program` that reads and writes ASCII text.
+pub struct Terminal<W = i64> {
+    program: Program<W>,
+}
+
+/// Text is the output of a program, read by a `Terminal`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Text<W = i64> {
+    /// Everything the program output that was an ASCII character.
+    pub text: String,
+
+    /// Everything the program output that was not an ASCII character, in
+    /// order.
+    pub values: Vec<W>,
+}
+
+/// Screen is a single screen of output, read by `Terminal::read_screen`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Screen<W = i64> {
+    rows: Vec<Vec<char>>,
+    values: Vec<W>,
+}
+
+/// AsciiError is returned by `Terminal::send_line` when the line holds a
+/// character that is not ASCII.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct AsciiError {
+    /// The offending character.
+    pub c: char,
+
+    /// The position of the character in the line, in bytes.
+    pub position: usize,
+}
+
+impl fmt::Display for AsciiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "non-ASCII character {:?} at {}", self.c, self.position)
+    }
+}
+
+impl error::Error for AsciiError {}
+
+impl From<AsciiError> for io::Error {
+    fn from(e: AsciiError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidInput, e)
+    }
+}
+
+impl<W: Word> Terminal<W> {
+    pub fn new(program: Program<W>) -> Self {
+        Self { program }
+    }
+
+    /// Returns the wrapped program.
+    pub fn program(&self) -> &Program<W> {
+        &self.program
+    }
+
+    /// Returns the wrapped program.
+    pub fn program_mut(&mut self) -> &mut Program<W> {
+        &mut self.program
+    }
+
+    /// Unwraps the program.
+    pub fn into_program(self) -> Program<W> {
+        self.program
+    }
+
+    /// Returns `true` if the program has halted, or run into one of its
+    /// limits, so that sending it more input will not get it going again.
+    pub fn is_halted(&self) -> bool {
+        !matches!(
+            self.program.reason_for_stop(),
+            None | Some(Stop::WaitingForInput)
+        )
+    }
+
+    /// Queues `line` as input for the program, followed by a newline.
+    ///
+    /// Nothing is queued if `line` holds a character that is not ASCII.
+    pub fn send_line(&mut self, line: &str) -> Result<&mut Self, AsciiError> {
+        if let Some((position, c)) = line.char_indices().find(|(_, c)| !c.is_ascii()) {
+            return Err(AsciiError { c, position });
+        }
+        for b in line.bytes().chain(Some(b'\n')) {
+            self.program.input(W::from_i64(b.into()));
+        }
+        Ok(self)
+    }
+
+    /// Runs the program until it stops, usually to wait for the next line of
+    /// input, and returns what it output.
+    pub fn read_until_prompt(&mut self) -> Result<Text<W>, IntcodeError> {
+        self.read(|_| false)
+    }
+
+    /// Runs the program until it outputs a blank line, or it stops, and
+    /// returns the lines it output before the blank line, as a screen.
+    ///
+    /// Programs that draw more than one screen (like day 17's live video
+    /// feed) separate each one with a blank line, so each call to
+    /// `read_screen` returns the next screen.
+    pub fn read_screen(&mut self) -> Result<Screen<W>, IntcodeError> {
+        let Text { text, values } = self.read(|text| text.ends_with("\n\n"))?;
+        let mut rows: Vec<Vec<char>> = text.lines().map(|l| l.chars().collect()).collect();
+        while rows.last().is_some_and(|row| row.is_empty()) {
+            rows.pop();
+        }
+        Ok(Screen { rows, values })
+    }
+
+    // Steps the program until it stops, or `done` returns `true` for the
+    // text it has output so far.
+    fn read<F>(&mut self, mut done: F) -> Result<Text<W>, IntcodeError>
+    where
+        F: FnMut(&str) -> bool,
+    {
+        let mut out = Text {
+            text: String::new(),
+            values: vec![],
+        };
+        loop {
+            match self.program.step()? {
+                Step::Output(v) => match to_ascii(&v) {
+                    Some(c) => {
+                        out.text.push(c);
+                        if done(&out.text) {
+                            return Ok(out);
+                        }
+                    }
+                    None => out.values.push(v),
+                },
+                Step::Stopped(_) => return Ok(out),
+                Step::Executed | Step::Watchpoint(_) => {}
+            }
+        }
+    }
+}
+
+// Returns the ASCII character `v` encodes, if any.
+fn to_ascii<W: Word>(v: &W) -> Option<char> {
+    match v.to_i64() {
+        Some(n) if (0..128).contains(&n) => Some(n as u8 as char),
+        _ => None,
+    }
+}
+
+impl<W> Screen<W> {
+    /// Returns each row of the screen, from top to bottom.
+    pub fn rows(&self) -> &[Vec<char>] {
+        &self.rows
+    }
+
+    /// Returns the character at column `x` of row `y`, if there is one.
+    pub fn get(&self, x: usize, y: usize) -> Option<char> {
+        self.rows.get(y).and_then(|row| row.get(x)).copied()
+    }
+
+    /// Returns the number of rows on the screen.
+    pub fn height(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// Returns the length of the longest row on the screen.
+    pub fn width(&self) -> usize {
+        self.rows.iter().map(|row| row.len()).max().unwrap_or(0)
+    }
+
+    /// Returns any values output while drawing the screen that were not
+    /// ASCII characters.
+    pub fn values(&self) -> &[W] {
+        &self.values
+    }
+}
+
+impl<W> fmt::Display for Screen<W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in &self.rows {
+            writeln!(f, "{}", row.iter().collect::<String>())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+use super::assemble;
+
+// Returns a program that outputs `text`, and then `values`, and halts.
+#[cfg(test)]
+fn printer(text: &str, values: &[i64]) -> Program {
+    let mut mem: Vec<i64> = vec![];
+    for v in text.bytes().map(i64::from).chain(values.iter().copied()) {
+        mem.extend(&[104, v]);
+    }
+    mem.push(99);
+    Program::from(mem)
+}
+
+#[test]
+fn test_read_until_prompt() {
+    // Prompts with "> ", and echoes the line it reads, followed by 1000.
+    let src = "
+                out #62
+                out #32
+        loop:   in -> [c]
+                out [c]
+                eq [c], #10 -> [t]
+                jf [t], #loop
+                out #1000
+                hlt
+        c:      data 0
+        t:      data 0
+    ";
+    let mut terminal = Terminal::new(Program::from(assemble(src).unwrap()));
+    let out = terminal.read_until_prompt().unwrap();
+    assert_eq!(out.text, "> ");
+    assert!(out.values.is_empty());
+    assert!(!terminal.is_halted());
+
+    let err = terminal.send_line("héllo").err().unwrap();
+    assert_eq!(
+        err,
+        AsciiError {
+            c: 'é',
+            position: 1
+        }
+    );
+
+    terminal.send_line("hello").unwrap();
+    let out = terminal.read_until_prompt().unwrap();
+    assert_eq!(out.text, "hello\n");
+    assert_eq!(out.values, vec![1000]);
+    assert!(terminal.is_halted());
+}
+
+#[test]
+fn test_read_screen() {
+    let mut terminal = Terminal::new(printer("#.\n.#\n\n##\n\nbye\n", &[4242]));
+
+    let screen = terminal.read_screen().unwrap();
+    assert_eq!(screen.rows(), &[vec!['#', '.'], vec!['.', '#']]);
+    assert_eq!(screen.get(1, 0), Some('.'));
+    assert_eq!(screen.get(2, 0), None);
+    assert_eq!((screen.width(), screen.height()), (2, 2));
+    assert_eq!(screen.to_string(), "#.\n.#\n");
+
+    let screen = terminal.read_screen().unwrap();
+    assert_eq!(screen.to_string(), "##\n");
+    assert!(!terminal.is_halted());
+
+    // The last screen ends when the program halts.
+    let screen = terminal.read_screen().unwrap();
+    assert_eq!(screen.to_string(), "bye\n");
+    assert_eq!(screen.values(), &[4242]);
+    assert!(terminal.is_halted());
+}
+
+#[test]
+fn test_limit_halts() {
+    let mut program = printer("hello\n", &[]);
+    program.set_output_limit(Some(2));
+    let mut terminal = Terminal::new(program);
+    let out = terminal.read_until_prompt().unwrap();
+    assert_eq!(out.text, "he");
+    assert_eq!(
+        terminal.program().reason_for_stop(),
+        Some(Stop::OutputLimit)
+    );
+    assert!(terminal.is_halted());
+}
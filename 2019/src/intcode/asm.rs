@@ -0,0 +1,493 @@
+//! An assembler for intcode programs.
+//!
+//! Source is written one instruction per line, using the following
+//! mnemonics:
+//!
+//! | Mnemonic | Opcode | Operands |
+//! |----------|--------|----------|
+//! | `add`    | 1      | a, b -> c |
+//! | `mul`    | 2      | a, b -> c |
+//! | `in`     | 3      | -> a     |
+//! | `out`    | 4      | a        |
+//! | `jt`     | 5      | a, b     |
+//! | `jf`     | 6      | a, b     |
+//! | `lt`     | 7      | a, b -> c |
+//! | `eq`     | 8      | a, b -> c |
+//! | `arb`    | 9      | a        |
+//! | `hlt`    | 99     |          |
+//!
+//! Operands are written as `#n` for immediate mode, `[rb+n]` (or `[rb-n]`)
+//! for relative mode, and `[n]` or plain `n` for positional mode.
+//! The `->` before an instruction's output operand is optional; a `,` works
+//! just as well.
+//!
+//! A line may start with one or more labels (`loop:`), which can then be
+//! used in place of a number in any operand, optionally with an offset
+//! (`#loop`, `[buf+2]`).
+//! The `data` directive emits its comma-separated values as-is.
+//! Everything after a `;` is a comment.
+//!
+//! A numeric label, such as `0012:`, asserts that the line starts at that
+//! address.
+//! This means the output of the disassembler can be fed straight back into
+//! the assembler.
+//!
+//! ```ignore
+//! start:  in -> [n]
+//!         eq [n], #8 -> [n]
+//!         out [n]
+//!         hlt
+//! n:      data 0
+//! ```
+use super::disasm::{Disassembler, Kind};
+use super::{Instruction, Parameter};
+use std::collections::HashMap;
+use std::error;
+use std::fmt;
+use std::io;
+
+/// Assemble the intcode source in `src` into memory that can be loaded with
+/// `Program::from`.
+pub fn assemble(src: &str) -> Result<Vec<i64>, AsmError> {
+    // First pass: parse each line, and figure out the address of each label.
+    let mut items: Vec<(usize, Item)> = vec![];
+    let mut labels: HashMap<String, i64> = HashMap::new();
+    let mut address: usize = 0;
+    for (i, line) in src.lines().enumerate() {
+        let lineno = i + 1;
+        let err = |kind| AsmError { line: lineno, kind };
+
+        // Strip comments.
+        let mut line = match line.find(';') {
+            Some(n) => &line[..n],
+            None => line,
+        }
+        .trim();
+
+        // Collect any labels at the start of the line.
+        while let Some(n) = line.find(':') {
+            let label = line[..n].trim();
+            if label.chars().all(|c| c.is_ascii_digit()) {
+                let want: usize = label
+                    .parse()
+                    .map_err(|_| err(AsmErrorKind::BadLabel(label.to_string())))?;
+                if want != address {
+                    return Err(err(AsmErrorKind::AddressMismatch { want, got: address }));
+                }
+            } else if is_ident(label) {
+                if labels.insert(label.to_string(), address as i64).is_some() {
+                    return Err(err(AsmErrorKind::DuplicateLabel(label.to_string())));
+                }
+            } else {
+                return Err(err(AsmErrorKind::BadLabel(label.to_string())));
+            }
+            line = line[n + 1..].trim();
+        }
+        if line.is_empty() {
+            continue;
+        }
+
+        let item = Item::parse(line).map_err(err)?;
+        let size = item.size();
+        items.push((lineno, item));
+        address += size;
+    }
+
+    // Second pass: resolve labels, and encode each item.
+    let mut mem: Vec<i64> = Vec::with_capacity(address);
+    for (lineno, item) in items {
+        let resolve = |e: &Expr| -> Result<i64, AsmError> {
+            e.resolve(&labels)
+                .map_err(|kind| AsmError { line: lineno, kind })
+        };
+        match item {
+            Item::Data(values) => {
+                for v in &values {
+                    mem.push(resolve(v)?);
+                }
+            }
+            Item::Op(opcode, operands) => {
+                let mut word = opcode;
+                let mut place = 100;
+                let mut params: Vec<i64> = vec![];
+                for (mode, expr) in &operands {
+                    word += mode * place;
+                    place *= 10;
+                    params.push(resolve(expr)?);
+                }
+                mem.push(word);
+                mem.extend(params);
+            }
+        }
+    }
+    Ok(mem)
+}
+
+/// Disassemble `mem` into source that `assemble` turns back into exactly the
+/// same memory.
+///
+/// This uses the same syntax as `Program::disassemble`, except that
+/// instructions whose opcode has unusual encodings (such as spare mode
+/// digits) are rendered as `DATA`, so they survive the round-trip.
+pub fn disassemble(mem: &[i64]) -> String {
+    let mut src = String::new();
+    for line in Disassembler::new(mem) {
+        let address = line.address;
+        let canonical = match &line.kind {
+            Kind::Instruction(inst) => inst.encode()[..] == mem[address..address + line.size()],
+            Kind::Data(_) => true,
+        };
+        if canonical {
+            src.push_str(&format!("{}\n", line));
+            continue;
+        }
+        for (i, n) in mem[address..address + line.size()].iter().enumerate() {
+            src.push_str(&format!("{:04}: DATA {}\n", address + i, n));
+        }
+    }
+    src
+}
+
+/// AsmError is returned by `assemble` when the source cannot be assembled.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AsmError {
+    /// The 1-based line number the error occurred on.
+    pub line: usize,
+
+    /// What went wrong.
+    pub kind: AsmErrorKind,
+}
+
+/// The different kinds of `AsmError`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum AsmErrorKind {
+    /// The mnemonic is not one of the known instructions or directives.
+    UnknownMnemonic(String),
+
+    /// The operand could not be parsed.
+    BadOperand(String),
+
+    /// The instruction was given the wrong number of operands.
+    OperandCount { want: usize, got: usize },
+
+    /// A label is not a valid identifier.
+    BadLabel(String),
+
+    /// A label was defined more than once.
+    DuplicateLabel(String),
+
+    /// An operand refers to a label that was never defined.
+    UndefinedLabel(String),
+
+    /// A numeric label did not match the address of the line.
+    AddressMismatch { want: usize, got: usize },
+}
+
+impl fmt::Display for AsmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match &self.kind {
+            AsmErrorKind::UnknownMnemonic(s) => write!(f, "unknown mnemonic {:?}", s),
+            AsmErrorKind::BadOperand(s) => write!(f, "bad operand {:?}", s),
+            AsmErrorKind::OperandCount { want, got } => {
+                write!(f, "expected {} operands, got {}", want, got)
+            }
+            AsmErrorKind::BadLabel(s) => write!(f, "bad label {:?}", s),
+            AsmErrorKind::DuplicateLabel(s) => write!(f, "duplicate label {:?}", s),
+            AsmErrorKind::UndefinedLabel(s) => write!(f, "undefined label {:?}", s),
+            AsmErrorKind::AddressMismatch { want, got } => {
+                write!(f, "expected address {:04}, got {:04}", want, got)
+            }
+        }
+    }
+}
+
+impl error::Error for AsmError {}
+
+impl From<AsmError> for io::Error {
+    fn from(e: AsmError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, e)
+    }
+}
+
+// A single, parsed line of source.
+enum Item {
+    // An opcode, and each of its operands' mode and value.
+    Op(i64, Vec<(i64, Expr)>),
+
+    // Values emitted by the `data` directive.
+    Data(Vec<Expr>),
+}
+
+impl Item {
+    fn parse(line: &str) -> Result<Self, AsmErrorKind> {
+        let (mnemonic, rest) = match line.find(char::is_whitespace) {
+            Some(n) => (&line[..n], line[n..].trim()),
+            None => (line, ""),
+        };
+        // The "->" before an output operand is just a fancy ",".
+        let rest = rest.replace("->", ",");
+        let rest = rest.trim().trim_start_matches(',');
+        let operands: Vec<&str> = if rest.is_empty() {
+            vec![]
+        } else {
+            rest.split(',').map(|s| s.trim()).collect()
+        };
+
+        let mnemonic = mnemonic.to_ascii_lowercase();
+        if mnemonic == "data" {
+            let values = operands
+                .iter()
+                .map(|s| Expr::parse(s))
+                .collect::<Result<Vec<Expr>, AsmErrorKind>>()?;
+            return Ok(Self::Data(values));
+        }
+
+        let (opcode, want) = match mnemonic.as_str() {
+            "add" => (1, 3),
+            "mul" => (2, 3),
+            "in" => (3, 1),
+            "out" => (4, 1),
+            "jt" => (5, 2),
+            "jf" => (6, 2),
+            "lt" => (7, 3),
+            "eq" => (8, 3),
+            "arb" => (9, 1),
+            "hlt" => (99, 0),
+            _ => {
+                return Err(AsmErrorKind::UnknownMnemonic(mnemonic));
+            }
+        };
+        if operands.len() != want {
+            return Err(AsmErrorKind::OperandCount {
+                want,
+                got: operands.len(),
+            });
+        }
+        let operands = operands
+            .iter()
+            .map(|s| parse_operand(s))
+            .collect::<Result<Vec<(i64, Expr)>, AsmErrorKind>>()?;
+        Ok(Self::Op(opcode, operands))
+    }
+
+    // Returns the number of memory cells the item assembles to.
+    fn size(&self) -> usize {
+        match self {
+            Self::Op(_, operands) => operands.len() + 1,
+            Self::Data(values) => values.len(),
+        }
+    }
+}
+
+// Parses an operand, returning its mode and value.
+fn parse_operand(s: &str) -> Result<(i64, Expr), AsmErrorKind> {
+    if let Some(v) = s.strip_prefix('#') {
+        return Ok((1, Expr::parse(v)?));
+    }
+    if let Some(v) = s.strip_prefix('[').and_then(|v| v.strip_suffix(']')) {
+        let v = v.trim();
+        if let Some(off) = v.strip_prefix("rb") {
+            let off = off.trim();
+            if off.is_empty() {
+                return Ok((2, Expr::Number(0)));
+            }
+            if off.starts_with('+') || off.starts_with('-') {
+                let off = off.strip_prefix('+').unwrap_or(off);
+                return Ok((2, Expr::parse(off)?));
+            }
+        }
+        return Ok((0, Expr::parse(v)?));
+    }
+    Ok((0, Expr::parse(s)?))
+}
+
+fn is_ident(s: &str) -> bool {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => {}
+        _ => return false,
+    }
+    chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+// A number, or a reference to a label with an optional offset.
+enum Expr {
+    Number(i64),
+    Label(String, i64),
+}
+
+impl Expr {
+    fn parse(s: &str) -> Result<Self, AsmErrorKind> {
+        let s = s.trim();
+        let bad = || AsmErrorKind::BadOperand(s.to_string());
+        if let Ok(n) = s.parse::<i64>() {
+            return Ok(Self::Number(n));
+        }
+
+        // A label, possibly followed by "+n" or "-n".
+        let (label, offset) = match s.find(['+', '-']) {
+            Some(n) => {
+                let offset: i64 = s[n..]
+                    .trim_start_matches('+')
+                    .trim()
+                    .parse()
+                    .map_err(|_| bad())?;
+                (s[..n].trim(), offset)
+            }
+            None => (s, 0),
+        };
+        if !is_ident(label) {
+            return Err(bad());
+        }
+        Ok(Self::Label(label.to_string(), offset))
+    }
+
+    fn resolve(&self, labels: &HashMap<String, i64>) -> Result<i64, AsmErrorKind> {
+        match self {
+            Self::Number(n) => Ok(*n),
+            Self::Label(l, offset) => match labels.get(l) {
+                Some(address) => Ok(address + offset),
+                None => Err(AsmErrorKind::UndefinedLabel(l.to_string())),
+            },
+        }
+    }
+}
+
+impl Instruction<i64> {
+    // Returns the canonical encoding of the instruction.
+    fn encode(&self) -> Vec<i64> {
+        let (opcode, params): (i64, Vec<&Parameter<i64>>) = match self {
+            Self::Add(a, b, c) => (1, vec![a, b, c]),
+            Self::Multiply(a, b, c) => (2, vec![a, b, c]),
+            Self::Input(a) => (3, vec![a]),
+            Self::Output(a) => (4, vec![a]),
+            Self::JumpIfTrue(a, b) => (5, vec![a, b]),
+            Self::JumpIfFalse(a, b) => (6, vec![a, b]),
+            Self::LessThan(a, b, c) => (7, vec![a, b, c]),
+            Self::Equals(a, b, c) => (8, vec![a, b, c]),
+            Self::RelativeBaseOffset(a) => (9, vec![a]),
+            Self::HCF => (99, vec![]),
+            Self::Custom(custom) => (
+                custom.opcode.into(),
+                custom.params.iter().flatten().collect(),
+            ),
+        };
+        let mut word = opcode;
+        let mut place = 100;
+        let mut v: Vec<i64> = vec![0];
+        for p in params {
+            let (mode, n) = match *p {
+                Parameter::Positional(n) => (0, n as i64),
+                Parameter::Immediate(n) => (1, n),
+                Parameter::Relative(n) => (2, n),
+            };
+            word += mode * place;
+            place *= 10;
+            v.push(n);
+        }
+        v[0] = word;
+        v
+    }
+}
+
+#[cfg(test)]
+use super::Program;
+
+#[test]
+fn test_assemble_modes() {
+    let mem = assemble("add [rb+3], #7 -> [100]\nmul [rb-2], 5, [rb]\nhlt").unwrap();
+    assert_eq!(mem, vec![1201, 3, 7, 100, 20202, -2, 5, 0, 99]);
+}
+
+#[test]
+fn test_assemble_large_example() {
+    // Outputs 999 if the input is less than 8, 1000 if it is equal to 8,
+    // or 1001 if it is greater than 8.
+    let src = "
+        ; Read the input, and compare it to 8.
+                in -> [n]
+                lt [n], #8 -> [cmp]
+                jt [cmp], #less
+                eq [n], #8 -> [cmp]
+                jt [cmp], #equal
+                out #1001
+                hlt
+        less:   out #999
+                hlt
+        equal:  out #1000
+                hlt
+        n:      data 0
+        cmp:    data 0
+    ";
+    let mem = assemble(src).unwrap();
+    for &(input, want) in &[(7, 999), (8, 1000), (9, 1001)] {
+        let mut program = Program::from(mem.clone());
+        let out = program.input(input).execute().unwrap();
+        assert_eq!(out, vec![want]);
+    }
+}
+
+#[test]
+fn test_assemble_boost_self() {
+    // Outputs a copy of itself.
+    let src = "
+        start:  arb #1
+                out [rb-1]
+                add [100], #1 -> [100]
+                eq [100], #end+1 -> [101]
+                jf [101], #start
+        end:    hlt
+    ";
+    let code = "109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99";
+    let mem = assemble(src).unwrap();
+    let want: Vec<i64> = code.split(',').map(|s| s.parse().unwrap()).collect();
+    assert_eq!(mem, want);
+}
+
+#[test]
+fn test_assemble_data() {
+    let mem = assemble("out [msg+1]\nhlt\nmsg: data 7, -8, msg").unwrap();
+    assert_eq!(mem, vec![4, 4, 99, 7, -8, 3]);
+
+    // Labels that start with "rb" are still positional.
+    let mem = assemble("out [rbuf]\nhlt\nrbuf: data 1").unwrap();
+    assert_eq!(mem, vec![4, 3, 99, 1]);
+}
+
+#[test]
+fn test_assemble_errors() {
+    let err = assemble("hlt\nfoo #1").unwrap_err();
+    assert_eq!(err.line, 2);
+    assert_eq!(err.kind, AsmErrorKind::UnknownMnemonic("foo".to_string()));
+
+    let err = assemble("add #1, #2").unwrap_err();
+    assert_eq!(err.kind, AsmErrorKind::OperandCount { want: 3, got: 2 });
+
+    let err = assemble("jt #1, #nowhere").unwrap_err();
+    assert_eq!(
+        err.kind,
+        AsmErrorKind::UndefinedLabel("nowhere".to_string())
+    );
+
+    let err = assemble("a: hlt\na: hlt").unwrap_err();
+    assert_eq!(err.line, 2);
+    assert_eq!(err.kind, AsmErrorKind::DuplicateLabel("a".to_string()));
+
+    let err = assemble("out [1!]").unwrap_err();
+    assert_eq!(err.kind, AsmErrorKind::BadOperand("1!".to_string()));
+
+    let err = assemble("0000: hlt\n0002: hlt").unwrap_err();
+    assert_eq!(err.kind, AsmErrorKind::AddressMismatch { want: 2, got: 1 });
+}
+
+#[test]
+fn test_round_trip() {
+    let code = "3,21,1008,21,8,20,1005,20,22,107,8,21,20,1006,20,31,1106,0,36,98,0,0,1002,21,125,20,4,20,1105,1,46,104,999,1105,1,46,1101,1000,1,20,4,20,1105,1,46,98,99";
+    let mem: Vec<i64> = code.split(',').map(|s| s.parse().unwrap()).collect();
+    assert_eq!(assemble(&disassemble(&mem)).unwrap(), mem);
+
+    // Unusual encodings, negative positions, and truncated instructions.
+    let mem = vec![10004, 5, 11199, 4, -1, 1101, 1];
+    assert_eq!(assemble(&disassemble(&mem)).unwrap(), mem);
+}
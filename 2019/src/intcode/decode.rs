@@ -0,0 +1,117 @@
+//! Predecoded instructions.
+//!
+//! Decoding an instruction means looking up how many parameters its opcode
+//! takes, and splitting out the mode of each parameter, which a program would
+//! otherwise do every time it executed the instruction.
+//! Instead, every address of a program's initial memory that holds a valid
+//! instruction is decoded once, when the program is loaded, and the decoded
+//! instructions are shared by all clones of the program, like its initial
+//! memory is.
+//!
+//! Since intcode programs can modify themselves, each program also keeps
+//! track of which addresses have been written to since it was loaded.
+//! A decoded instruction overlapping any of them is stale, and the program
+//! falls back to decoding the instruction from its current memory.
+use super::{Dialect, Instruction, Word};
+use std::sync::Arc;
+
+/// The most cells a single instruction spans: an opcode, and three
+/// parameters.
+const MAX_LEN: usize = 4;
+
+/// A decoded instruction, and the address of the instruction after it.
+pub(super) type Decoded<W> = (Instruction<W>, usize);
+
+#[derive(Clone)]
+pub(super) struct Cache<W> {
+    // The decoded instruction at each address of the program's initial
+    // memory, if it holds a valid one.
+    slots: Arc<Vec<Option<Decoded<W>>>>,
+
+    // Which slots are stale, one bit per address.
+    stale: Stale,
+
+    enabled: bool,
+}
+
+/// Stale records which addresses of a program's initial memory have been
+/// overwritten since it was loaded.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub(super) struct Stale(Vec<u64>);
+
+impl<W: Word> Cache<W> {
+    /// Decodes every instruction in `mem`, in `dialect`.
+    pub(super) fn new(mem: &[W], dialect: &Dialect<W>) -> Self {
+        let slots = (0..mem.len()).map(|ip| decode(mem, ip, dialect)).collect();
+        Self {
+            slots: Arc::new(slots),
+            stale: Stale(vec![0; mem.len().div_ceil(64)]),
+            enabled: true,
+        }
+    }
+
+    /// Returns the decoded instruction at `ip`, unless there is none, or it
+    /// is stale.
+    pub(super) fn get(&self, ip: usize) -> Option<&Decoded<W>> {
+        let slot = self.slots.get(ip)?;
+        if !self.enabled || self.stale.contains(ip) {
+            return None;
+        }
+        slot.as_ref()
+    }
+
+    /// Marks any instruction that overlaps `address` as stale.
+    pub(super) fn write(&mut self, address: usize) {
+        let start = address.saturating_sub(MAX_LEN - 1);
+        let end = self.slots.len().min(address + 1);
+        for ip in start..end {
+            self.stale.insert(ip);
+        }
+    }
+
+    /// Decodes every instruction in `mem`, replacing the program's old
+    /// instructions.
+    pub(super) fn reload(&mut self, mem: &[W], dialect: &Dialect<W>) {
+        *self = Self {
+            enabled: self.enabled,
+            ..Self::new(mem, dialect)
+        };
+    }
+
+    /// Marks every instruction as fresh again, once the program's memory has
+    /// been reset to its initial state.
+    pub(super) fn reset(&mut self) {
+        self.stale.0.iter_mut().for_each(|bits| *bits = 0);
+    }
+
+    pub(super) fn stale(&self) -> &Stale {
+        &self.stale
+    }
+
+    pub(super) fn set_stale(&mut self, stale: &Stale) {
+        self.stale.clone_from(stale);
+    }
+
+    pub(super) fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+}
+
+/// Decodes the instruction at `ip` in `mem`, if there is a valid one in
+/// `dialect`.
+pub(super) fn decode<W: Word>(mem: &[W], ip: usize, dialect: &Dialect<W>) -> Option<Decoded<W>> {
+    let np = dialect.num_params(mem.get(ip)?.to_i64()?)?;
+    let next = ip + np + 1;
+    let inst = Instruction::parse(ip, mem.get(ip..next)?, dialect).ok()?;
+    Some((inst, next))
+}
+
+impl Stale {
+    fn contains(&self, ip: usize) -> bool {
+        self.0[ip / 64] & (1 << (ip % 64)) != 0
+    }
+
+    fn insert(&mut self, ip: usize) {
+        self.0[ip / 64] |= 1 << (ip % 64);
+    }
+}
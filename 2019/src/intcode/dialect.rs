@@ -0,0 +1,405 @@
+//! Intcode dialects: which opcodes a program understands.
+//!
+//! By default, a program understands every opcode from 2019's puzzles:
+//! 1 through 9, and 99.
+//! A `Dialect` can take opcodes away, like `Dialect::day2`, which only has
+//! the instructions from day 2, or add new ones.
+//!
+//! A new opcode is registered with the number of parameters it takes, and an
+//! implementation of the `Opcode` trait, which is handed an `Args` to read
+//! and write its parameters with.
+//! Its parameters take the same modes as any other instruction's.
+//!
+//! ```ignore
+//! // 10: swap a, b
+//! struct Swap;
+//!
+//! impl Opcode for Swap {
+//!     fn mnemonic(&self) -> &str {
+//!         "SWAP"
+//!     }
+//!
+//!     fn execute(&self, args: &mut Args<'_>) -> Result<(), IntcodeError> {
+//!         let (a, b) = (args.get(0)?, args.get(1)?);
+//!         args.set(0, b)?;
+//!         args.set(1, a)
+//!     }
+//! }
+//!
+//! let mut dialect = Dialect::new();
+//! dialect.register(10, 2, Swap);
+//! program.set_dialect(dialect);
+//! ```
+use super::{Instruction, IntcodeError, IntcodeInput, Parameter, Program, Step, Stop, Watch, Word};
+use std::fmt;
+use std::sync::Arc;
+
+/// The most parameters an instruction can take.
+pub(super) const MAX_PARAMS: usize = 3;
+
+/// Opcode defines what a custom instruction does.
+pub trait Opcode<W = i64>: Send + Sync {
+    /// Returns the name the disassembler shows for the instruction.
+    fn mnemonic(&self) -> &str;
+
+    /// Executes the instruction.
+    ///
+    /// The static analyzer assumes a custom instruction never jumps, or
+    /// writes to anything but its parameters.
+    fn execute(&self, args: &mut Args<'_, W>) -> Result<(), IntcodeError>;
+}
+
+impl<W> fmt::Debug for dyn Opcode<W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Opcode({})", self.mnemonic())
+    }
+}
+
+/// A decoded custom instruction.
+#[derive(Debug)]
+pub(super) struct Custom<W> {
+    pub(super) opcode: u8,
+    pub(super) op: Arc<dyn Opcode<W>>,
+    pub(super) params: [Option<Parameter<W>>; MAX_PARAMS],
+}
+
+/// Dialect is the set of opcodes a program understands.
+pub struct Dialect<W = i64> {
+    // What each two-digit opcode does, if anything.
+    ops: Vec<Op<W>>,
+}
+
+enum Op<W> {
+    Invalid,
+    Builtin(usize),
+    Custom(usize, Arc<dyn Opcode<W>>),
+}
+
+impl<W> Clone for Op<W> {
+    fn clone(&self) -> Self {
+        match self {
+            Self::Invalid => Self::Invalid,
+            Self::Builtin(np) => Self::Builtin(*np),
+            Self::Custom(np, op) => Self::Custom(*np, op.clone()),
+        }
+    }
+}
+
+impl<W> Clone for Dialect<W> {
+    fn clone(&self) -> Self {
+        Self {
+            ops: self.ops.clone(),
+        }
+    }
+}
+
+impl<W: Word> Default for Dialect<W> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<W: Word> Dialect<W> {
+    /// Returns the dialect of 2019's later puzzles, with opcodes 1 through
+    /// 9, and 99.
+    pub fn new() -> Self {
+        let ops = (0..100)
+            .map(|opcode| match Instruction::<W>::num_params(opcode) {
+                Some(np) => Op::Builtin(np),
+                None => Op::Invalid,
+            })
+            .collect();
+        Self { ops }
+    }
+
+    /// Returns the strict dialect of day 2, which only has the add (1),
+    /// multiply (2), and halt (99) instructions.
+    pub fn day2() -> Self {
+        let mut dialect = Self::new();
+        for opcode in 3..=9 {
+            dialect.remove(opcode);
+        }
+        dialect
+    }
+
+    /// Registers `op` as opcode `opcode`, taking `params` parameters,
+    /// replacing whatever the opcode did before.
+    ///
+    /// Panics if `opcode` is not between 1 and 99, or `params` is more than
+    /// 3, since an instruction word only has room for three parameter
+    /// modes.
+    pub fn register<O>(&mut self, opcode: u8, params: usize, op: O) -> &mut Self
+    where
+        O: Opcode<W> + 'static,
+    {
+        assert!(
+            (1..100).contains(&opcode),
+            "invalid opcode {}: must be between 1 and 99",
+            opcode
+        );
+        assert!(
+            params <= MAX_PARAMS,
+            "opcode {} cannot take {} parameters",
+            opcode,
+            params
+        );
+        self.ops[opcode as usize] = Op::Custom(params, Arc::new(op));
+        self
+    }
+
+    /// Removes `opcode`, so that executing it is an
+    /// `IntcodeError::UnknownOpcode`.
+    pub fn remove(&mut self, opcode: u8) -> &mut Self {
+        if let Some(op) = self.ops.get_mut(opcode as usize) {
+            *op = Op::Invalid;
+        }
+        self
+    }
+
+    /// Returns the number of parameters taken by the instruction `word`, or
+    /// `None` if its opcode is not part of the dialect.
+    pub(super) fn num_params(&self, word: i64) -> Option<usize> {
+        if word < 0 {
+            return None;
+        }
+        match self.ops[(word % 100) as usize] {
+            Op::Invalid => None,
+            Op::Builtin(np) | Op::Custom(np, _) => Some(np),
+        }
+    }
+
+    /// Returns the custom opcode for the instruction `word`, if it has one.
+    pub(super) fn custom(&self, word: i64) -> Option<&Arc<dyn Opcode<W>>> {
+        match &self.ops[(word % 100) as usize] {
+            Op::Custom(_, op) => Some(op),
+            _ => None,
+        }
+    }
+}
+
+impl<W: Word> Program<W> {
+    // Executes `custom`, the instruction at the instruction pointer, where
+    // `next` is the address of the instruction after it.
+    pub(super) fn execute_custom<I>(
+        &mut self,
+        custom: &Custom<W>,
+        next: usize,
+        input: &mut I,
+    ) -> Result<Step<W>, IntcodeError>
+    where
+        I: IntcodeInput<W> + ?Sized,
+    {
+        let mut next_input = || input.next_input();
+        let mut args = Args::new(self, &custom.params, &mut next_input);
+        custom.op.execute(&mut args)?;
+        let Args {
+            output,
+            jump,
+            watch,
+            waiting,
+            ..
+        } = args;
+        if waiting {
+            self.reason = Some(Stop::WaitingForInput);
+            return Ok(Step::Stopped(Stop::WaitingForInput));
+        }
+
+        self.ip = jump.unwrap_or(next);
+        let step = match (output, watch) {
+            (Some(v), _) => Step::Output(v),
+            (None, Some(watch)) => Step::Watchpoint(watch),
+            (None, None) => Step::Executed,
+        };
+        Ok(step)
+    }
+}
+
+/// Args gives a custom instruction access to its parameters, and to the
+/// program executing it.
+pub struct Args<'a, W = i64> {
+    program: &'a mut Program<W>,
+    params: &'a [Option<Parameter<W>>; MAX_PARAMS],
+    input: &'a mut dyn FnMut() -> Option<W>,
+    output: Option<W>,
+    jump: Option<usize>,
+    watch: Option<Watch<W>>,
+    waiting: bool,
+}
+
+impl<'a, W: Word> Args<'a, W> {
+    fn new(
+        program: &'a mut Program<W>,
+        params: &'a [Option<Parameter<W>>; MAX_PARAMS],
+        input: &'a mut dyn FnMut() -> Option<W>,
+    ) -> Self {
+        Self {
+            program,
+            params,
+            input,
+            output: None,
+            jump: None,
+            watch: None,
+            waiting: false,
+        }
+    }
+
+    // Returns parameter `i`, panicking if the instruction does not have
+    // that many.
+    fn param(&self, i: usize) -> &'a Parameter<W> {
+        match self.params.get(i) {
+            Some(Some(param)) => param,
+            _ => panic!("instruction has no parameter {}", i),
+        }
+    }
+
+    /// Returns the value of parameter `i`.
+    pub fn get(&mut self, i: usize) -> Result<W, IntcodeError> {
+        let param = self.param(i);
+        self.program.resolve_param(param)
+    }
+
+    /// Writes `value` to where parameter `i` points.
+    pub fn set(&mut self, i: usize, value: W) -> Result<(), IntcodeError> {
+        let param = self.param(i);
+        let address = self.program.resolve_out_ptr(param)?;
+        if let Step::Watchpoint(watch) = self.program.write_mem(address, value) {
+            self.watch = Some(watch);
+        }
+        Ok(())
+    }
+
+    /// Returns the next input value.
+    ///
+    /// If there is none, the program stops with `Stop::WaitingForInput`
+    /// once the instruction returns, without moving on to the next
+    /// instruction, so that it is executed again once there is more input.
+    /// An instruction should not make any writes before asking for input.
+    pub fn input(&mut self) -> Option<W> {
+        let v = self.program.input.pop_front().or_else(|| (self.input)());
+        self.waiting = v.is_none();
+        if let Some(v) = &v {
+            self.program.record_input(v);
+        }
+        v
+    }
+
+    /// Outputs `value`.
+    /// An instruction can only output one value; any value output before
+    /// the last one is lost.
+    pub fn output(&mut self, value: W) {
+        self.output = Some(value);
+    }
+
+    /// Jumps to `address`, rather than moving on to the next instruction.
+    pub fn jump(&mut self, address: &W) -> Result<(), IntcodeError> {
+        self.jump = Some(self.program.check_address(address)?);
+        Ok(())
+    }
+
+    /// Returns the program's relative base.
+    pub fn rel_base(&self) -> W {
+        self.program.rel_base()
+    }
+
+    /// Sets the program's relative base.
+    pub fn set_rel_base(&mut self, rel_base: W) {
+        self.program.rel_base = rel_base;
+    }
+
+    /// Returns the program executing the instruction.
+    pub fn program(&self) -> &Program<W> {
+        self.program
+    }
+}
+
+// 10: swap a, b
+#[cfg(test)]
+struct Swap;
+
+#[cfg(test)]
+impl Opcode for Swap {
+    fn mnemonic(&self) -> &str {
+        "SWAP"
+    }
+
+    fn execute(&self, args: &mut Args<'_>) -> Result<(), IntcodeError> {
+        let (a, b) = (args.get(0)?, args.get(1)?);
+        args.set(0, b)?;
+        args.set(1, a)
+    }
+}
+
+// 20: reads a value, and outputs it doubled
+#[cfg(test)]
+struct Double;
+
+#[cfg(test)]
+impl Opcode for Double {
+    fn mnemonic(&self) -> &str {
+        "DBL"
+    }
+
+    fn execute(&self, args: &mut Args<'_>) -> Result<(), IntcodeError> {
+        if let Some(v) = args.input() {
+            args.output(v * 2);
+        }
+        Ok(())
+    }
+}
+
+// 11: jumps to a
+#[cfg(test)]
+struct Jump;
+
+#[cfg(test)]
+impl Opcode for Jump {
+    fn mnemonic(&self) -> &str {
+        "JMP"
+    }
+
+    fn execute(&self, args: &mut Args<'_>) -> Result<(), IntcodeError> {
+        let target = args.get(0)?;
+        args.jump(&target)
+    }
+}
+
+#[test]
+fn test_day2() {
+    let mut program = Program::from("1,0,0,0,99");
+    program.set_dialect(Dialect::day2());
+    program.execute().unwrap();
+    assert_eq!(program.peek_mem(0), 2);
+
+    let mut program = Program::from("1,0,0,0,104,1,99");
+    program.set_dialect(Dialect::day2());
+    let err = program.execute().unwrap_err();
+    assert_eq!(err, IntcodeError::UnknownOpcode { ip: 4, word: 104 });
+}
+
+#[test]
+fn test_custom_opcodes() {
+    let mut dialect = Dialect::new();
+    dialect.register(10, 2, Swap);
+    dialect.register(11, 1, Jump);
+    dialect.register(20, 0, Double);
+
+    let mut program = Program::from("10,4,5,99,7,8");
+    program.set_dialect(dialect.clone());
+    let lines: Vec<String> = program.disassemble().map(|l| l.to_string()).collect();
+    assert_eq!(lines[0], "0000: SWAP [4], [5]");
+    program.execute().unwrap();
+    assert_eq!((program.peek_mem(4), program.peek_mem(5)), (8, 7));
+
+    // A custom instruction waits for input, like any other.
+    let mut program = Program::from("20,20,99");
+    program.set_dialect(dialect.clone());
+    assert_eq!(program.execute().unwrap(), vec![]);
+    assert_eq!(program.reason_for_stop(), Some(Stop::WaitingForInput));
+    assert_eq!(program.ip(), 0);
+    assert_eq!(program.input(21).input(4).execute().unwrap(), vec![42, 8]);
+    assert_eq!(program.reason_for_stop(), Some(Stop::HCF));
+
+    let mut program = Program::from("111,4,99,99,104,7,99");
+    program.set_dialect(dialect);
+    assert_eq!(program.execute().unwrap(), vec![7]);
+}
@@ -0,0 +1,227 @@
+//! Render intcode memory as readable assembly.
+//!
+//! Each decoded instruction is rendered on its own line, prefixed with its
+//! address, with its parameters written in one of three ways:
+//!
+//! * `[100]` for a positional parameter;
+//! * `#7` for an immediate parameter;
+//! * `[rb+3]` for a relative parameter.
+//!
+//! Parameters that an instruction writes to are shown after a `->`, so a
+//! line might look like `0012: ADD [rb+3], #7 -> [100]`.
+//! Any memory that does not decode as an instruction is rendered as
+//! `DATA n`.
+use super::decode::decode;
+use super::{Dialect, Instruction, Parameter, Program, Word};
+use std::borrow::Cow;
+use std::fmt;
+use std::sync::Arc;
+
+impl<W: Word> Program<W> {
+    /// Returns a `Disassembler` over the program's current memory.
+    pub fn disassemble(&self) -> Disassembler<'_, W> {
+        Disassembler {
+            mem: self.mem.as_slice(),
+            dialect: self.dialect.clone(),
+            pos: 0,
+        }
+    }
+}
+
+/// Disassembler walks a slice of intcode memory, yielding a `Line` for each
+/// instruction, or data value, that it finds.
+pub struct Disassembler<'a, W: Clone = i64> {
+    mem: Cow<'a, [W]>,
+    dialect: Arc<Dialect<W>>,
+    pos: usize,
+}
+
+impl<'a, W: Word> Disassembler<'a, W> {
+    /// Return a new Disassembler that starts at the beginning of `mem`, and
+    /// decodes the opcodes of the default `Dialect`.
+    pub fn new(mem: &'a [W]) -> Self {
+        Self {
+            mem: Cow::Borrowed(mem),
+            dialect: Arc::new(Dialect::new()),
+            pos: 0,
+        }
+    }
+}
+
+impl<'a, W: Word> Iterator for Disassembler<'a, W> {
+    type Item = Line<W>;
+
+    fn next(&mut self) -> Option<Line<W>> {
+        if self.pos >= self.mem.len() {
+            return None;
+        }
+
+        let address = self.pos;
+        let word = self.mem[address].clone();
+        let line = match decode(&self.mem, address, &self.dialect) {
+            Some((inst, next)) => {
+                self.pos = next;
+                Line {
+                    address,
+                    kind: Kind::Instruction(inst),
+                }
+            }
+            None => {
+                self.pos += 1;
+                Line {
+                    address,
+                    kind: Kind::Data(word),
+                }
+            }
+        };
+        Some(line)
+    }
+}
+
+/// Line is a single line of disassembly.
+pub struct Line<W = i64> {
+    pub(super) address: usize,
+    pub(super) kind: Kind<W>,
+}
+
+pub(super) enum Kind<W> {
+    Instruction(Instruction<W>),
+    Data(W),
+}
+
+impl<W> Line<W> {
+    /// Returns the memory address the line starts at.
+    pub fn address(&self) -> usize {
+        self.address
+    }
+
+    /// Returns the number of memory cells covered by the line.
+    pub fn size(&self) -> usize {
+        match &self.kind {
+            Kind::Instruction(inst) => inst.len(),
+            Kind::Data(_) => 1,
+        }
+    }
+
+    /// Returns `true` if the line could not be decoded as an instruction.
+    pub fn is_data(&self) -> bool {
+        matches!(self.kind, Kind::Data(_))
+    }
+}
+
+impl<W: Word> fmt::Display for Line<W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}: ", self.address)?;
+        match &self.kind {
+            Kind::Instruction(inst) => write!(f, "{}", inst),
+            Kind::Data(n) => write!(f, "DATA {}", n),
+        }
+    }
+}
+
+impl<W> Instruction<W> {
+    // Returns the number of memory cells taken up by the instruction,
+    // including the opcode.
+    fn len(&self) -> usize {
+        match self {
+            Self::Add(..) | Self::Multiply(..) | Self::LessThan(..) | Self::Equals(..) => 4,
+            Self::JumpIfTrue(..) | Self::JumpIfFalse(..) => 3,
+            Self::Input(_) | Self::Output(_) | Self::RelativeBaseOffset(_) => 2,
+            Self::HCF => 1,
+            Self::Custom(custom) => 1 + custom.params.iter().flatten().count(),
+        }
+    }
+}
+
+impl<W: Word> fmt::Display for Instruction<W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Add(a, b, c) => write!(f, "ADD {}, {} -> {}", a, b, c),
+            Self::Multiply(a, b, c) => write!(f, "MUL {}, {} -> {}", a, b, c),
+            Self::Input(a) => write!(f, "IN -> {}", a),
+            Self::Output(a) => write!(f, "OUT {}", a),
+            Self::JumpIfTrue(a, b) => write!(f, "JT {}, {}", a, b),
+            Self::JumpIfFalse(a, b) => write!(f, "JF {}, {}", a, b),
+            Self::LessThan(a, b, c) => write!(f, "LT {}, {} -> {}", a, b, c),
+            Self::Equals(a, b, c) => write!(f, "EQ {}, {} -> {}", a, b, c),
+            Self::RelativeBaseOffset(a) => write!(f, "ARB {}", a),
+            Self::HCF => write!(f, "HLT"),
+            Self::Custom(custom) => {
+                write!(f, "{}", custom.op.mnemonic())?;
+                for (i, param) in custom.params.iter().flatten().enumerate() {
+                    let sep = if i == 0 { " " } else { ", " };
+                    write!(f, "{}{}", sep, param)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl<W: Word> fmt::Display for Parameter<W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Positional(n) => write!(f, "[{}]", n),
+            Self::Immediate(n) => write!(f, "#{}", n),
+            Self::Relative(n) if n.is_negative() => write!(f, "[rb{}]", n),
+            Self::Relative(n) => write!(f, "[rb+{}]", n),
+        }
+    }
+}
+
+#[cfg(test)]
+fn listing(code: &str) -> Vec<String> {
+    Program::from(code)
+        .disassemble()
+        .map(|line| line.to_string())
+        .collect()
+}
+
+#[test]
+fn test_disassemble_modes() {
+    assert_eq!(
+        listing("22201,3,-2,100,99"),
+        vec!["0000: ADD [rb+3], [rb-2] -> [rb+100]", "0004: HLT"]
+    );
+    assert_eq!(
+        listing("1201,3,7,100"),
+        vec!["0000: ADD [rb+3], #7 -> [100]"]
+    );
+}
+
+#[test]
+fn test_disassemble_large_example() {
+    let code = "3,21,1008,21,8,20,1005,20,22,107,8,21,20,1006,20,31,1106,0,36,98,0,0,1002,21,125,20,4,20,1105,1,46,104,999,1105,1,46,1101,1000,1,20,4,20,1105,1,46,98,99";
+    let lines = listing(code);
+    assert_eq!(lines[0], "0000: IN -> [21]");
+    assert_eq!(lines[1], "0002: EQ [21], #8 -> [20]");
+    assert_eq!(lines[2], "0006: JT [20], #22");
+    assert_eq!(lines[3], "0009: LT #8, [21] -> [20]");
+    assert_eq!(lines[4], "0013: JF [20], #31");
+    assert_eq!(lines[5], "0016: JF #0, #36");
+    assert_eq!(lines[6], "0019: DATA 98");
+    assert_eq!(lines[lines.len() - 1], "0046: HLT");
+}
+
+#[test]
+fn test_disassemble_data() {
+    // An unknown opcode, an invalid mode, and a truncated instruction.
+    assert_eq!(
+        listing("42,301,4,5,1101,1"),
+        vec![
+            "0000: DATA 42",
+            "0001: DATA 301",
+            "0002: OUT [5]",
+            "0004: DATA 1101",
+            "0005: DATA 1"
+        ]
+    );
+}
+
+#[test]
+fn test_disassemble_covers_memory() {
+    let code = "109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99";
+    let program = Program::from(code);
+    let total: usize = program.disassemble().map(|line| line.size()).sum();
+    assert_eq!(total, 16);
+}
@@ -0,0 +1,291 @@
+//! Drive intcode programs from async code.
+//!
+//! `Program::into_async` splits a program into an `AsyncProgram`, which
+//! yields the program's output one value at a time, and an `InputSink`, which
+//! feeds it input.
+//! While the program is waiting for input, `AsyncProgram::next_output` is
+//! suspended, and it is woken up again by the next value sent to the sink.
+//!
+//! Only `std::future` and `std::task` are used, so any executor can drive
+//! the program.
+//! A program that runs for a long time without producing any output yields
+//! to the executor every so often, so that it can be raced against a
+//! timeout, or run alongside other programs on the same thread.
+use super::{IntcodeError, IntcodeInput, Program, Step, Stop, Word};
+use std::collections::VecDeque;
+use std::future::poll_fn;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::task::{Context, Poll, Waker};
+
+/// The most instructions `AsyncProgram::poll_output` executes before
+/// yielding to the executor.
+const BUDGET: usize = 10_000;
+
+/// AsyncProgram runs a program from async code.
+pub struct AsyncProgram<W = i64> {
+    program: Program<W>,
+    shared: Arc<Mutex<Shared<W>>>,
+}
+
+/// InputSink sends input to an `AsyncProgram`.
+///
+/// Sinks can be cloned, so that more than one task can send input to the
+/// same program.
+/// Once every sink has been dropped, a program waiting for input has no way
+/// to get any more, and it finishes.
+pub struct InputSink<W = i64> {
+    shared: Arc<Mutex<Shared<W>>>,
+}
+
+// The state shared between a program, and its sinks.
+struct Shared<W> {
+    input: VecDeque<W>,
+
+    // Woken when input arrives, or the last sink is dropped.
+    waker: Option<Waker>,
+
+    // The number of sinks that have not been dropped.
+    sinks: usize,
+}
+
+impl<W: Word> Program<W> {
+    /// Wraps the program for use from async code.
+    ///
+    /// Returns the wrapped program, and the sink to send its input to.
+    /// Any input given to the program with `Program::input` is used up
+    /// first.
+    pub fn into_async(self) -> (AsyncProgram<W>, InputSink<W>) {
+        let shared = Arc::new(Mutex::new(Shared {
+            input: VecDeque::new(),
+            waker: None,
+            sinks: 1,
+        }));
+        let sink = InputSink {
+            shared: shared.clone(),
+        };
+        let program = AsyncProgram {
+            program: self,
+            shared,
+        };
+        (program, sink)
+    }
+}
+
+impl<W: Word> AsyncProgram<W> {
+    /// Runs the program until it outputs a value, and returns it.
+    ///
+    /// Returns `None` once the program stops for good: when it halts, runs
+    /// into one of its limits, or needs input after every `InputSink` has
+    /// been dropped.
+    /// `Program::reason_for_stop` says which.
+    pub async fn next_output(&mut self) -> Result<Option<W>, IntcodeError> {
+        poll_fn(|cx| self.poll_output(cx)).await
+    }
+
+    /// Polls the program for its next output value, as `next_output` does.
+    pub fn poll_output(&mut self, cx: &mut Context<'_>) -> Poll<Result<Option<W>, IntcodeError>> {
+        let mut input = SinkInput(&self.shared);
+        for _ in 0..BUDGET {
+            match self.program.step_with(&mut input)? {
+                Step::Output(v) => return Poll::Ready(Ok(Some(v))),
+                Step::Stopped(Stop::WaitingForInput) => {
+                    // Input may have arrived since the program looked, so
+                    // check again before suspending.
+                    let mut shared = lock(&self.shared);
+                    if !shared.input.is_empty() {
+                        continue;
+                    }
+                    if shared.sinks == 0 {
+                        return Poll::Ready(Ok(None));
+                    }
+                    shared.waker = Some(cx.waker().clone());
+                    return Poll::Pending;
+                }
+                Step::Stopped(_) => return Poll::Ready(Ok(None)),
+                Step::Executed | Step::Watchpoint(_) => {}
+            }
+        }
+
+        // Let something else run, and pick up where we left off after.
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+
+    /// Returns the program.
+    pub fn program(&self) -> &Program<W> {
+        &self.program
+    }
+
+    /// Returns the program, discarding any input that has been sent to it,
+    /// but not read yet.
+    pub fn into_program(self) -> Program<W> {
+        self.program
+    }
+}
+
+impl<W> InputSink<W> {
+    /// Sends `v` to the program, waking it up if it is waiting for input.
+    pub fn send(&self, v: W) {
+        let waker = {
+            let mut shared = lock(&self.shared);
+            shared.input.push_back(v);
+            shared.waker.take()
+        };
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+impl<W> Clone for InputSink<W> {
+    fn clone(&self) -> Self {
+        lock(&self.shared).sinks += 1;
+        Self {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<W> Drop for InputSink<W> {
+    fn drop(&mut self) {
+        let waker = {
+            let mut shared = lock(&self.shared);
+            shared.sinks -= 1;
+            match shared.sinks {
+                0 => shared.waker.take(),
+                _ => None,
+            }
+        };
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+// Locks `shared`, ignoring any poisoning, since its state is consistent
+// between every change made to it.
+fn lock<W>(shared: &Mutex<Shared<W>>) -> MutexGuard<'_, Shared<W>> {
+    shared.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+// Reads the input sent to a program's sinks.
+struct SinkInput<'a, W>(&'a Mutex<Shared<W>>);
+
+impl<'a, W> IntcodeInput<W> for SinkInput<'a, W> {
+    fn next_input(&mut self) -> Option<W> {
+        lock(self.0).input.pop_front()
+    }
+}
+
+#[cfg(test)]
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::atomic::{AtomicUsize, Ordering},
+    task::Wake,
+};
+
+// Counts how many times it has been woken.
+#[cfg(test)]
+#[derive(Default)]
+struct Counter(AtomicUsize);
+
+#[cfg(test)]
+impl Wake for Counter {
+    fn wake(self: Arc<Self>) {
+        self.0.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+type Task<'a> = Pin<Box<dyn Future<Output = ()> + 'a>>;
+
+// A minimal executor, which polls each of `tasks` in turn, on the current
+// thread, until they have all finished.
+// It panics if the tasks that are left are not woken, since they would never
+// finish.
+#[cfg(test)]
+fn run_all(mut tasks: Vec<Task<'_>>) {
+    let counter = Arc::new(Counter::default());
+    let waker = Waker::from(counter.clone());
+    let mut cx = Context::from_waker(&waker);
+    while !tasks.is_empty() {
+        let woken = counter.0.load(Ordering::SeqCst);
+        tasks.retain_mut(|task| task.as_mut().poll(&mut cx).is_pending());
+        if !tasks.is_empty() && counter.0.load(Ordering::SeqCst) == woken {
+            panic!("deadlock: {} tasks were never woken", tasks.len());
+        }
+    }
+}
+
+#[test]
+fn test_next_output() {
+    let counter = Arc::new(Counter::default());
+    let waker = Waker::from(counter.clone());
+    let mut cx = Context::from_waker(&waker);
+
+    // Echoes its input, until it reads a zero.
+    let (mut program, sink) = Program::from("3,11,1006,11,10,4,11,1105,1,0,99,0").into_async();
+    assert!(program.poll_output(&mut cx).is_pending());
+    assert_eq!(counter.0.load(Ordering::SeqCst), 0);
+
+    sink.send(5);
+    assert_eq!(counter.0.load(Ordering::SeqCst), 1);
+    assert_eq!(program.poll_output(&mut cx), Poll::Ready(Ok(Some(5))));
+
+    // Once every sink has been dropped, a waiting program finishes.
+    let other = sink.clone();
+    drop(sink);
+    assert!(program.poll_output(&mut cx).is_pending());
+    drop(other);
+    assert_eq!(counter.0.load(Ordering::SeqCst), 2);
+    assert_eq!(program.poll_output(&mut cx), Poll::Ready(Ok(None)));
+    let program = program.into_program();
+    assert_eq!(program.reason_for_stop(), Some(Stop::WaitingForInput));
+
+    // A program that never outputs anything still yields.
+    let (mut program, _sink) = Program::from("1105,1,0").into_async();
+    assert!(program.poll_output(&mut cx).is_pending());
+    assert_eq!(counter.0.load(Ordering::SeqCst), 3);
+
+    let (mut program, _sink) = Program::from("42").into_async();
+    let err = IntcodeError::UnknownOpcode { ip: 0, word: 42 };
+    assert_eq!(program.poll_output(&mut cx), Poll::Ready(Err(err)));
+}
+
+#[test]
+fn test_async_feedback_loop() {
+    // Day 7's amplifiers, each running as its own task.
+    let code =
+        "3,26,1001,26,-4,26,3,27,1002,27,2,27,1,27,26,27,4,27,1001,28,-1,28,1005,28,6,99,0,0,5";
+
+    let mut amps = vec![];
+    let mut sinks = vec![];
+    for &phase in &[9, 8, 7, 6, 5] {
+        let mut program = Program::from(code);
+        program.input(phase);
+        let (program, sink) = program.into_async();
+        amps.push(program);
+        sinks.push(sink);
+    }
+    sinks[0].send(0);
+    sinks.rotate_left(1);
+
+    let signal = std::cell::Cell::new(0);
+    let tasks = amps
+        .into_iter()
+        .zip(sinks)
+        .map(|(mut amp, next)| {
+            let signal = &signal;
+            Box::pin(async move {
+                while let Some(v) = amp.next_output().await.unwrap() {
+                    signal.set(v);
+                    next.send(v);
+                }
+                assert_eq!(amp.program().reason_for_stop(), Some(Stop::HCF));
+            }) as Task<'_>
+        })
+        .collect();
+    run_all(tasks);
+    assert_eq!(signal.get(), 139629729);
+}
@@ -0,0 +1,403 @@
+//! Differential fuzzing for the intcode VM.
+//!
+//! Random programs are run by `Program`, in each of its configurations, and
+//! by `Reference`, a small interpreter written straight from the puzzle
+//! descriptions, and have to end up with the same output, memory, and reason
+//! for stopping.
+//! The programs come from three places: the example programs from the
+//! puzzles, mutated copies of them, and programs generated from random,
+//! valid instructions.
+//! On top of that, `Program` is run over arbitrary memory, which must never
+//! make it panic.
+//!
+//! Every program runs with a memory limit and an instruction limit, since an
+//! arbitrary program can loop forever, or legitimately ask for more memory
+//! than there is.
+//! Each test case is generated from its own seed, which is reported when it
+//! fails.
+use super::{
+    stream::NoInput, Backend, Instruction, Program, Stop, LARGE_EXAMPLE, MAX_MEMORY, MODE_DIVISORS,
+    QUINE,
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::collections::VecDeque;
+use std::panic::{self, AssertUnwindSafe};
+
+const MEMORY_LIMIT: usize = 4096;
+const INSTRUCTION_LIMIT: u64 = 1_000;
+
+/// The example programs the fuzzer starts from.
+const SEEDS: &[&str] = &[
+    QUINE,
+    LARGE_EXAMPLE,
+    "3,9,8,9,10,9,4,9,99,-1,8",
+    "3,3,1107,-1,8,3,4,3,99",
+    "3,12,6,12,15,1,13,14,13,4,13,99,-1,0,1,9",
+    "1102,34915192,34915192,7,4,7,99,0",
+    "104,1125899906842624,99",
+    "1,9,10,3,2,3,11,0,99,30,40,50",
+];
+
+/// Reference is a deliberately simple intcode interpreter, used as the
+/// oracle for `Program`.
+struct Reference {
+    mem: Vec<i64>,
+    ip: usize,
+    rel_base: i64,
+    input: VecDeque<i64>,
+    output: Vec<i64>,
+    executed: u64,
+}
+
+#[derive(Clone, Copy)]
+enum Param {
+    Positional(usize),
+    Immediate(i64),
+    Relative(i64),
+}
+
+// Any error is reported as the address of the faulting instruction.
+type Fault = usize;
+
+impl Reference {
+    fn new(mem: &[i64], input: &[i64]) -> Self {
+        Self {
+            mem: mem.to_vec(),
+            ip: 0,
+            rel_base: 0,
+            input: input.iter().copied().collect(),
+            output: vec![],
+            executed: 0,
+        }
+    }
+
+    fn run(&mut self) -> Result<Stop, Fault> {
+        loop {
+            if let Some(stop) = self.step()? {
+                return Ok(stop);
+            }
+        }
+    }
+
+    fn read(&self, address: usize) -> i64 {
+        self.mem.get(address).copied().unwrap_or(0)
+    }
+
+    fn write(&mut self, address: usize, v: i64) {
+        if address >= self.mem.len() {
+            self.mem.resize(address + 1, 0);
+        }
+        self.mem[address] = v;
+    }
+
+    fn address(&self, v: i64) -> Result<usize, Fault> {
+        if v < 0 || v as u64 >= MAX_MEMORY as u64 {
+            return Err(self.ip);
+        }
+        Ok(v as usize)
+    }
+
+    // Relative addresses never wrap around.
+    fn relative(&self, n: i64) -> Result<usize, Fault> {
+        self.address(self.rel_base.checked_add(n).ok_or(self.ip)?)
+    }
+
+    fn get(&mut self, param: Param) -> Result<i64, Fault> {
+        match param {
+            Param::Positional(address) => Ok(self.read(address)),
+            Param::Immediate(v) => Ok(v),
+            Param::Relative(n) => {
+                // Reading a relative address allocates it, as it does in
+                // `Program`, which matters for whether a later instruction
+                // near the end of memory is truncated.
+                let address = self.relative(n)?;
+                if address >= self.mem.len() {
+                    self.mem.resize(address + 1, 0);
+                }
+                Ok(self.mem[address])
+            }
+        }
+    }
+
+    fn out_ptr(&self, param: Param) -> Result<usize, Fault> {
+        match param {
+            Param::Positional(address) => Ok(address),
+            Param::Immediate(_) => Err(self.ip),
+            Param::Relative(n) => self.relative(n),
+        }
+    }
+
+    // Executes one instruction, returning why the program stopped, if it
+    // did.
+    fn step(&mut self) -> Result<Option<Stop>, Fault> {
+        let word = self.read(self.ip);
+        let np = match word % 100 {
+            1 | 2 | 7 | 8 => 3,
+            5 | 6 => 2,
+            3 | 4 | 9 => 1,
+            99 => 0,
+            _ => return Err(self.ip),
+        };
+        if self.ip + np + 1 > self.mem.len() {
+            return Err(self.ip);
+        }
+        let mut params = [Param::Immediate(0); 3];
+        for (i, param) in params.iter_mut().enumerate().take(np) {
+            let v = self.read(self.ip + i + 1);
+            *param = match (word / MODE_DIVISORS[i]) % 10 {
+                0 => Param::Positional(self.address(v)?),
+                1 => Param::Immediate(v),
+                2 => Param::Relative(v),
+                _ => return Err(self.ip),
+            };
+        }
+
+        if self.executed >= INSTRUCTION_LIMIT {
+            return Ok(Some(Stop::InstructionLimit));
+        }
+        for &param in &params[..np] {
+            let address = match param {
+                Param::Positional(address) => Some(address),
+                Param::Relative(n) => self.relative(n).ok(),
+                Param::Immediate(_) => None,
+            };
+            if address.is_some_and(|address| address >= MEMORY_LIMIT) {
+                return Ok(Some(Stop::MemoryLimit));
+            }
+        }
+
+        let [a, b, c] = params;
+        match word % 100 {
+            1 | 2 | 7 | 8 => {
+                let (x, y) = (self.get(a)?, self.get(b)?);
+                let address = self.out_ptr(c)?;
+                let v = match word % 100 {
+                    1 => x.wrapping_add(y),
+                    2 => x.wrapping_mul(y),
+                    7 => (x < y) as i64,
+                    _ => (x == y) as i64,
+                };
+                self.write(address, v);
+            }
+            3 => {
+                let address = self.out_ptr(a)?;
+                match self.input.pop_front() {
+                    Some(v) => self.write(address, v),
+                    None => return Ok(Some(Stop::WaitingForInput)),
+                }
+            }
+            4 => {
+                let v = self.get(a)?;
+                self.output.push(v);
+            }
+            5 | 6 => {
+                let test = self.get(a)?;
+                if (test != 0) == (word % 100 == 5) {
+                    let target = self.get(b)?;
+                    self.ip = self.address(target)?;
+                    self.executed += 1;
+                    return Ok(None);
+                }
+            }
+            9 => {
+                let n = self.get(a)?;
+                self.rel_base = self.rel_base.wrapping_add(n);
+            }
+            _ => return Ok(Some(Stop::HCF)),
+        }
+        self.ip += np + 1;
+        self.executed += 1;
+        Ok(None)
+    }
+}
+
+// Runs `mem` with `input` on the reference interpreter, and on `Program`
+// with each memory backend, with and without its decode cache, and panics
+// if any of them disagree.
+fn check(mem: &[i64], input: &[i64], seed: u64) {
+    let mut want = Reference::new(mem, input);
+    let want_result = want.run();
+
+    for &backend in &[Backend::Flat, Backend::Paged] {
+        for &cached in &[true, false] {
+            let context = format!(
+                "seed {}: {:?} with input {:?} ({:?}, cached: {})",
+                seed, mem, input, backend, cached
+            );
+            let mut program = Program::from(mem.to_vec());
+            program
+                .set_backend(backend)
+                .set_decode_cache(cached)
+                .set_memory_limit(Some(MEMORY_LIMIT))
+                .set_instruction_limit(Some(INSTRUCTION_LIMIT));
+            for &v in input {
+                program.input(v);
+            }
+
+            let mut output = vec![];
+            let result =
+                panic::catch_unwind(AssertUnwindSafe(|| program.run(&mut NoInput, &mut output)));
+            let result = match result {
+                Ok(result) => result.map_err(|e| e.ip()),
+                Err(_) => panic!("{}: the VM panicked", context),
+            };
+            assert_eq!(result, want_result, "{}", context);
+            assert_eq!(output, want.output, "{}", context);
+            assert_eq!(program.ip(), want.ip, "{}", context);
+            assert_eq!(program.rel_base(), want.rel_base, "{}", context);
+
+            let len = program.mem.len().max(want.mem.len());
+            let got: Vec<i64> = (0..len).map(|i| program.peek_mem(i)).collect();
+            let want_mem: Vec<i64> = (0..len).map(|i| want.read(i)).collect();
+            assert_eq!(got, want_mem, "{}", context);
+        }
+    }
+}
+
+// Returns a random value for an immediate parameter, or an input, which is
+// usually small, but sometimes large enough to overflow.
+fn random_value(rng: &mut StdRng) -> i64 {
+    match rng.gen_range(0, 10) {
+        0 => rng.gen(),
+        1 => *[i64::MIN, i64::MAX, -1, 1 << 32]
+            .get(rng.gen_range(0, 4))
+            .unwrap(),
+        _ => rng.gen_range(-10, 20),
+    }
+}
+
+fn random_input(rng: &mut StdRng) -> Vec<i64> {
+    let n = rng.gen_range(0, 5);
+    (0..n).map(|_| random_value(rng)).collect()
+}
+
+// Returns a random program, made up of valid instructions, that ends in a
+// halt, followed by some data.
+// Its parameters mostly point into the program, or at its instructions, so
+// that it reads, writes, and jumps around itself, modifying its own code
+// along the way.
+fn random_program(rng: &mut StdRng) -> Vec<i64> {
+    let mut opcodes: Vec<i64> = (0..rng.gen_range(1, 24))
+        .map(|_| match rng.gen_range(0, 20) {
+            0 => 99,
+            n => n % 9 + 1,
+        })
+        .collect();
+    opcodes.push(99);
+
+    let mut starts = vec![];
+    let mut len = 0;
+    for &opcode in &opcodes {
+        starts.push(len as i64);
+        len += 1 + Instruction::<i64>::num_params(opcode).unwrap();
+    }
+    let len = len + 16;
+
+    let mut mem = vec![];
+    for &opcode in &opcodes {
+        let np = Instruction::<i64>::num_params(opcode).unwrap();
+        let mut word = opcode;
+        let mut params = vec![];
+        for (i, &divisor) in MODE_DIVISORS.iter().enumerate().take(np) {
+            // The last parameter of these instructions is written to.
+            let writes = matches!(opcode, 1 | 2 | 7 | 8) && i == 2 || opcode == 3;
+            let jumps = matches!(opcode, 5 | 6) && i == 1;
+            let mode = match rng.gen_range(0, 4) {
+                _ if writes => rng.gen_range(0, 2) * 2,
+                0 | 1 if jumps || opcode == 9 => 1,
+                n => n % 3,
+            };
+            let v = match mode {
+                0 => rng.gen_range(0, len as i64),
+                1 if jumps => starts[rng.gen_range(0, starts.len())],
+                1 if opcode == 9 => rng.gen_range(-2, 8),
+                1 => random_value(rng),
+                _ => rng.gen_range(0, 16),
+            };
+            word += mode * divisor;
+            params.push(v);
+        }
+        mem.push(word);
+        mem.extend(params);
+    }
+    while mem.len() < len {
+        mem.push(rng.gen_range(0, len as i64));
+    }
+    mem
+}
+
+// Returns a copy of `mem` with a few of its cells replaced, with random
+// values, or random instructions.
+fn mutate(rng: &mut StdRng, mem: &[i64]) -> Vec<i64> {
+    let mut mem = mem.to_vec();
+    for _ in 0..rng.gen_range(1, 4) {
+        let i = rng.gen_range(0, mem.len());
+        mem[i] = match rng.gen_range(0, 3) {
+            0 => random_value(rng),
+            1 => random_word(rng),
+            _ => mem[i] ^ (1 << rng.gen_range(0, 8)),
+        };
+    }
+    mem
+}
+
+// Returns a random instruction word, valid or not, with random modes.
+fn random_word(rng: &mut StdRng) -> i64 {
+    let mut word = rng.gen_range(0, 100);
+    for &divisor in &MODE_DIVISORS {
+        word += rng.gen_range(0, 10) * divisor;
+    }
+    word
+}
+
+// Returns arbitrary memory, which mostly does not hold a valid program.
+fn random_memory(rng: &mut StdRng) -> Vec<i64> {
+    (0..rng.gen_range(1, 32))
+        .map(|_| match rng.gen_range(0, 4) {
+            0 => rng.gen(),
+            1 => random_word(rng),
+            _ => random_value(rng),
+        })
+        .collect()
+}
+
+fn parse(code: &str) -> Vec<i64> {
+    code.split(',').map(|s| s.parse().unwrap()).collect()
+}
+
+#[test]
+fn test_fuzz_seeds() {
+    for (seed, code) in SEEDS.iter().enumerate() {
+        let mem = parse(code);
+        for input in &[vec![], vec![0], vec![7], vec![8], vec![9, 1]] {
+            check(&mem, input, seed as u64);
+        }
+    }
+}
+
+#[test]
+fn test_fuzz_random_programs() {
+    for seed in 0..500 {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mem = random_program(&mut rng);
+        check(&mem, &random_input(&mut rng), seed);
+    }
+}
+
+#[test]
+fn test_fuzz_mutated_seeds() {
+    for seed in 0..500 {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mem = mutate(&mut rng, &parse(SEEDS[seed as usize % SEEDS.len()]));
+        check(&mem, &random_input(&mut rng), seed);
+    }
+}
+
+#[test]
+fn test_fuzz_no_panic() {
+    for seed in 0..1000 {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mem = random_memory(&mut rng);
+        check(&mem, &random_input(&mut rng), seed);
+    }
+}
@@ -0,0 +1,299 @@
+//! Stepping intcode programs backwards.
+//!
+//! With its history turned on, a program keeps an undo log with an entry for
+//! every instruction it executes: the instruction pointer, relative base,
+//! and reason for stopping from before the instruction, any input it used
+//! up, and the old value of each memory address it wrote to.
+//! Undoing an entry puts all of that back, so a program can be stepped
+//! backwards one instruction at a time, or rewound to the instruction that
+//! last wrote to an address, which is the quickest way to find out where a
+//! value came from in a program that modifies itself.
+//!
+//! Only the instructions a program executes are recorded; changes made with
+//! `Program::set_mem` are not.
+//! An instruction that fails partway through, such as a custom instruction
+//! that writes to memory and then returns an error, is undone as soon as it
+//! fails, so that the program is left as it was before the instruction.
+use super::{IntcodeError, Program, Step, Stop, Word};
+
+/// History is the undo log of a program.
+#[derive(Clone, Debug)]
+pub(super) struct History<W> {
+    entries: Vec<Entry<W>>,
+
+    // The old value of every address written to, in order, for all entries.
+    writes: Vec<(usize, W)>,
+}
+
+// The state of a program from before it executed an instruction.
+#[derive(Clone, Debug)]
+struct Entry<W> {
+    ip: usize,
+    rel_base: W,
+    reason: Option<Stop>,
+    executed: u64,
+    outputs: u64,
+
+    // The length of memory, which the instruction may have grown.
+    len: usize,
+
+    // The input the instruction used up, if any.
+    input: Option<W>,
+
+    // Where the instruction's writes start in `History::writes`.
+    writes: usize,
+}
+
+impl<W: Word> Program<W> {
+    /// Sets whether the program records its history, so that it can be
+    /// stepped backwards with `step_back` and `rewind_to_write`.
+    ///
+    /// Turning history off throws away whatever has been recorded.
+    pub fn set_history(&mut self, enabled: bool) -> &mut Self {
+        self.history = match enabled {
+            true => Some(self.history.take().unwrap_or(History {
+                entries: vec![],
+                writes: vec![],
+            })),
+            false => None,
+        };
+        self
+    }
+
+    /// Returns the number of instructions that can be undone.
+    pub fn history_len(&self) -> usize {
+        self.history.as_ref().map_or(0, |h| h.entries.len())
+    }
+
+    /// Undoes the last `n` instructions the program executed, or as many as
+    /// it has recorded, if that is fewer.
+    /// Returns the number of instructions undone.
+    pub fn step_back(&mut self, n: usize) -> usize {
+        (0..n).take_while(|_| self.undo()).count()
+    }
+
+    /// Rewinds the program to just before the last instruction that wrote
+    /// to `address`, and returns that instruction's address, which is where
+    /// the instruction pointer is left.
+    ///
+    /// If no recorded instruction wrote to `address`, the program is left as
+    /// it is, and `None` is returned.
+    pub fn rewind_to_write(&mut self, address: usize) -> Option<usize> {
+        let history = self.history.as_ref()?;
+        let (i, _) = history
+            .writes
+            .iter()
+            .enumerate()
+            .rfind(|(_, (a, _))| *a == address)?;
+        let n = history.entries.len() - history.entries.partition_point(|e| e.writes <= i) + 1;
+        self.step_back(n);
+        Some(self.ip)
+    }
+
+    // Starts recording an instruction about to be executed.
+    pub(super) fn begin_entry(&mut self) {
+        let entry = Entry {
+            ip: self.ip,
+            rel_base: self.rel_base.clone(),
+            reason: self.reason,
+            executed: self.executed,
+            outputs: self.outputs,
+            len: self.mem.len(),
+            input: None,
+            writes: 0,
+        };
+        if let Some(history) = &mut self.history {
+            history.entries.push(Entry {
+                writes: history.writes.len(),
+                ..entry
+            });
+        }
+    }
+
+    // Finishes recording the instruction, given what executing it returned.
+    // The entry is kept only if the instruction was executed; one that
+    // failed is undone, since it may have written to memory before failing.
+    pub(super) fn end_entry(&mut self, step: &Result<Step<W>, IntcodeError>) {
+        match step {
+            Ok(Step::Stopped(_)) => {
+                if let Some(history) = &mut self.history {
+                    if let Some(entry) = history.entries.pop() {
+                        history.writes.truncate(entry.writes);
+                    }
+                }
+            }
+            Err(_) => {
+                self.undo();
+            }
+            Ok(_) => {}
+        }
+    }
+
+    // Records that the instruction being executed is about to write to
+    // `address`.
+    pub(super) fn record_write(&mut self, address: usize) {
+        let old = self.peek_mem(address);
+        if let Some(history) = &mut self.history {
+            history.writes.push((address, old));
+        }
+    }
+
+    // Records that the instruction being executed used up the input `v`.
+    pub(super) fn record_input(&mut self, v: &W) {
+        if let Some(entry) = self.history.as_mut().and_then(|h| h.entries.last_mut()) {
+            entry.input = Some(v.clone());
+        }
+    }
+
+    // Throws away the recorded history, once the program's state has been
+    // replaced.
+    pub(super) fn clear_history(&mut self) {
+        if let Some(history) = &mut self.history {
+            history.entries.clear();
+            history.writes.clear();
+        }
+    }
+
+    // Undoes the last recorded instruction, if there is one.
+    fn undo(&mut self) -> bool {
+        let history = match &mut self.history {
+            Some(history) => history,
+            None => return false,
+        };
+        let entry = match history.entries.pop() {
+            Some(entry) => entry,
+            None => return false,
+        };
+        let writes = history.writes.split_off(entry.writes);
+        for (address, old) in writes.into_iter().rev() {
+            self.set_mem(address, old);
+        }
+        self.mem.truncate(entry.len);
+        self.ip = entry.ip;
+        self.rel_base = entry.rel_base;
+        self.reason = entry.reason;
+        self.executed = entry.executed;
+        self.outputs = entry.outputs;
+        if let Some(v) = entry.input {
+            self.input.push_front(v);
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+use super::{assemble, Args, Backend, Dialect, Opcode};
+
+// 10: writes 1 to a, and then fails writing 2 to b, if b is immediate
+#[cfg(test)]
+struct WriteTwice;
+
+#[cfg(test)]
+impl Opcode for WriteTwice {
+    fn mnemonic(&self) -> &str {
+        "WR2"
+    }
+
+    fn execute(&self, args: &mut Args<'_>) -> Result<(), IntcodeError> {
+        args.set(0, 1)?;
+        args.set(1, 2)
+    }
+}
+
+#[test]
+fn test_step_back() {
+    // Reads two numbers, and outputs their sum, using the relative base.
+    let code = "109,20,203,0,203,1,22201,0,1,2,204,2,99";
+    for &backend in &[Backend::Flat, Backend::Paged] {
+        let mut program = Program::from(code);
+        program.set_backend(backend).set_history(true);
+        program.input(3).input(4);
+
+        let mut saves = vec![program.save()];
+        while let Ok(step) = program.step() {
+            if let Step::Stopped(_) = step {
+                break;
+            }
+            saves.push(program.save());
+        }
+        assert_eq!(program.history_len(), 5);
+        assert_eq!(program.peek_mem(22), 7);
+
+        // Every step backwards puts back the state from before the
+        // instruction, including the input it used up, and how far memory
+        // had grown.
+        saves.pop();
+        while let Some(save) = saves.pop() {
+            assert_eq!(program.step_back(1), 1);
+            assert_eq!(program.save(), save);
+        }
+        assert_eq!(program.step_back(1), 0);
+        assert_eq!(program.execute().unwrap(), vec![7]);
+
+        // Stepping back over several instructions at once lands in the same
+        // place.
+        assert_eq!(program.step_back(3), 3);
+        assert_eq!(program.ip(), 4);
+        assert_eq!(program.execute().unwrap(), vec![7]);
+    }
+}
+
+#[test]
+fn test_rewind_to_write() {
+    // Counts down from 3, patching the operand of its output instruction on
+    // every pass, and then overwrites the count one last time.
+    let code = assemble(
+        "
+    loop:   add [n], #-1 -> [n]
+            add [n], #0 -> [out+1]
+    out:    out #0
+            jt [n], #loop
+            add #42, #0 -> [n]
+            hlt
+    n:      data 3
+    ",
+    )
+    .unwrap();
+    let mut program = Program::from(code);
+    program.set_history(true);
+    assert_eq!(program.execute().unwrap(), vec![2, 1, 0]);
+    let n = program.history_len();
+
+    // The last write to `n` was the final add.
+    assert_eq!(program.rewind_to_write(18), Some(13));
+    assert_eq!(program.peek_mem(18), 0);
+    assert_eq!(program.history_len(), n - 1);
+
+    // Before that, it was the countdown.
+    assert_eq!(program.rewind_to_write(18), Some(0));
+    assert_eq!(program.peek_mem(18), 1);
+
+    // The output instruction was last patched by the second add, on the
+    // previous pass.
+    assert_eq!(program.rewind_to_write(9), Some(4));
+    assert_eq!(program.peek_mem(9), 2);
+
+    assert_eq!(program.rewind_to_write(100), None);
+    assert_eq!(program.ip(), 4);
+}
+
+#[test]
+fn test_failed_instruction() {
+    let mut dialect = Dialect::new();
+    dialect.register(10, 2, WriteTwice);
+
+    // Adds 2 and 3 into [9], and then overwrites it with a custom
+    // instruction that fails partway through.
+    let mut program = Program::from("1101,2,3,9,1010,9,0,99,0,0");
+    program.set_dialect(dialect).set_history(true);
+    let err = program.execute().unwrap_err();
+    assert_eq!(err, IntcodeError::ImmediateWrite { ip: 4, word: 1010 });
+
+    // The failed instruction's write has been undone, and only the add is
+    // left to step back through.
+    assert_eq!(program.ip(), 4);
+    assert_eq!(program.peek_mem(9), 5);
+    assert_eq!(program.history_len(), 1);
+    assert_eq!(program.step_back(1), 1);
+    assert_eq!(program.peek_mem(9), 0);
+}
@@ -0,0 +1,226 @@
+//! Memory backends for intcode programs.
+//!
+//! A program's memory is either a flat `Vec`, which is the fastest to read
+//! and write, or a set of copy-on-write pages, which makes cloning (forking)
+//! a program cheap: clones share all of their pages, and a page is only
+//! copied once one of the clones writes to it.
+//! Either way, memory grows as the program writes past the end of it, and
+//! any memory past the end reads as zero.
+use super::Word;
+use std::borrow::Cow;
+use std::fmt;
+use std::sync::Arc;
+
+// The number of cells in a single page of paged memory.
+const PAGE_SIZE: usize = 512;
+
+/// Backend selects how a program's memory is stored.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Backend {
+    /// A single, flat `Vec`.
+    Flat,
+
+    /// Copy-on-write pages, shared between clones of a program.
+    Paged,
+}
+
+#[derive(Clone)]
+pub(super) enum Memory<W> {
+    Flat(Vec<W>),
+    Paged(Pages<W>),
+}
+
+#[derive(Clone)]
+pub(super) struct Pages<W> {
+    // Pages that have never been written to are `None`, and read as zero.
+    pages: Vec<Option<Arc<Vec<W>>>>,
+    len: usize,
+}
+
+impl<W> From<Vec<W>> for Memory<W> {
+    fn from(v: Vec<W>) -> Self {
+        Self::Flat(v)
+    }
+}
+
+impl<W: Word> Memory<W> {
+    /// Returns memory holding `v`, stored in `backend`.
+    pub(super) fn new(v: &[W], backend: Backend) -> Self {
+        match backend {
+            Backend::Flat => Self::Flat(v.to_vec()),
+            Backend::Paged => {
+                let pages = v
+                    .chunks(PAGE_SIZE)
+                    .map(|chunk| {
+                        let mut page = chunk.to_vec();
+                        page.resize(PAGE_SIZE, W::from_i64(0));
+                        Some(Arc::new(page))
+                    })
+                    .collect();
+                Self::Paged(Pages {
+                    pages,
+                    len: v.len(),
+                })
+            }
+        }
+    }
+
+    pub(super) fn backend(&self) -> Backend {
+        match self {
+            Self::Flat(_) => Backend::Flat,
+            Self::Paged(_) => Backend::Paged,
+        }
+    }
+
+    /// Returns the number of cells of memory that have been allocated.
+    pub(super) fn len(&self) -> usize {
+        match self {
+            Self::Flat(v) => v.len(),
+            Self::Paged(p) => p.len,
+        }
+    }
+
+    /// Returns the value at `pos`, or zero if `pos` is past the end of
+    /// memory.
+    pub(super) fn get(&self, pos: usize) -> W {
+        let cell = match self {
+            Self::Flat(v) => v.get(pos),
+            Self::Paged(p) if pos < p.len => match p.pages.get(pos / PAGE_SIZE) {
+                Some(Some(page)) => page.get(pos % PAGE_SIZE),
+                _ => None,
+            },
+            Self::Paged(_) => None,
+        };
+        cell.cloned().unwrap_or_else(|| W::from_i64(0))
+    }
+
+    /// Sets the value at `pos`, growing memory if needed.
+    pub(super) fn set(&mut self, pos: usize, value: W) {
+        let len = pos.checked_add(1).expect("address out of range");
+        self.grow(len);
+        match self {
+            Self::Flat(v) => v[pos] = value,
+            Self::Paged(p) => {
+                let page = p.pages[pos / PAGE_SIZE]
+                    .get_or_insert_with(|| Arc::new(vec![W::from_i64(0); PAGE_SIZE]));
+                Arc::make_mut(page)[pos % PAGE_SIZE] = value;
+            }
+        }
+    }
+
+    /// Grows memory so that it is at least `len` cells long.
+    /// The new cells are zeroed.
+    pub(super) fn grow(&mut self, len: usize) {
+        if len <= self.len() {
+            return;
+        }
+        match self {
+            Self::Flat(v) => v.resize(len, W::from_i64(0)),
+            Self::Paged(p) => {
+                p.len = len;
+                let n = len.div_ceil(PAGE_SIZE);
+                if n > p.pages.len() {
+                    p.pages.resize(n, None);
+                }
+            }
+        }
+    }
+
+    /// Shrinks memory back down to `len` cells, if it is any longer.
+    pub(super) fn truncate(&mut self, len: usize) {
+        if len >= self.len() {
+            return;
+        }
+        match self {
+            Self::Flat(v) => v.truncate(len),
+            Self::Paged(p) => {
+                // Zero the rest of the last page, so that growing memory again
+                // does not bring back what was there.
+                if let Some(Some(page)) = p.pages.get_mut(len / PAGE_SIZE) {
+                    let end = PAGE_SIZE.min(p.len - len / PAGE_SIZE * PAGE_SIZE);
+                    for cell in &mut Arc::make_mut(page)[len % PAGE_SIZE..end] {
+                        *cell = W::from_i64(0);
+                    }
+                }
+                p.pages.truncate(len.div_ceil(PAGE_SIZE));
+                p.len = len;
+            }
+        }
+    }
+
+    /// Replaces the contents of memory with `v`, keeping the same backend.
+    pub(super) fn load(&mut self, v: &[W]) {
+        match self {
+            Self::Flat(mem) => {
+                mem.clear();
+                mem.extend_from_slice(v);
+            }
+            Self::Paged(_) => *self = Self::new(v, Backend::Paged),
+        }
+    }
+
+    /// Returns the contents of memory as a contiguous slice, which is only
+    /// copied if the memory is paged.
+    pub(super) fn as_slice(&self) -> Cow<'_, [W]> {
+        match self {
+            Self::Flat(v) => Cow::Borrowed(v),
+            Self::Paged(_) => Cow::Owned((0..self.len()).map(|i| self.get(i)).collect()),
+        }
+    }
+}
+
+impl<W: Word> PartialEq for Memory<W> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && (0..self.len()).all(|i| self.get(i) == other.get(i))
+    }
+}
+
+impl<W: Word> Eq for Memory<W> {}
+
+impl<W: Word> fmt::Debug for Memory<W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.as_slice().iter()).finish()
+    }
+}
+
+#[test]
+fn test_paged_get_set() {
+    let mut mem = Memory::new(&[1_i64, 2, 3], Backend::Paged);
+    assert_eq!(mem.len(), 3);
+    assert_eq!(mem.get(2), 3);
+    assert_eq!(mem.get(3), 0);
+
+    // Writing well past the end grows memory, without allocating the pages
+    // in between.
+    mem.set(PAGE_SIZE * 4 + 1, 7);
+    assert_eq!(mem.len(), PAGE_SIZE * 4 + 2);
+    assert_eq!(mem.get(PAGE_SIZE * 4 + 1), 7);
+    assert_eq!(mem.get(PAGE_SIZE * 2), 0);
+    if let Memory::Paged(p) = &mem {
+        assert_eq!(p.pages.iter().filter(|page| page.is_some()).count(), 2);
+    }
+
+    let flat = Memory::new(&mem.as_slice(), Backend::Flat);
+    assert_eq!(flat, mem);
+}
+
+#[test]
+fn test_paged_copy_on_write() {
+    let v: Vec<i64> = (0..PAGE_SIZE as i64 * 3).collect();
+    let a = Memory::new(&v, Backend::Paged);
+    let mut b = a.clone();
+    b.set(PAGE_SIZE + 1, -1);
+    assert_eq!(a.get(PAGE_SIZE + 1), PAGE_SIZE as i64 + 1);
+    assert_eq!(b.get(PAGE_SIZE + 1), -1);
+
+    // Only the page that was written to has been copied.
+    if let (Memory::Paged(a), Memory::Paged(b)) = (&a, &b) {
+        let shared: Vec<bool> = a
+            .pages
+            .iter()
+            .zip(&b.pages)
+            .map(|(x, y)| Arc::ptr_eq(x.as_ref().unwrap(), y.as_ref().unwrap()))
+            .collect();
+        assert_eq!(shared, vec![true, false, true]);
+    }
+}
@@ -0,0 +1,584 @@
+//! Wire several intcode programs together.
+//!
+//! A `Network` owns a number of `Program`s, and routes the output of each
+//! machine into the input of another, according to its topology:
+//!
+//! * a **chain** sends the output of each machine to the next one, with the
+//!   output of the last machine becoming the output of the network;
+//! * a **ring** is a chain where the last machine also feeds the first
+//!   (like the amplifiers in day 7);
+//! * **packets** treats the output of every machine as a stream of
+//!   `(address, x, y)` triples, and delivers each `(x, y)` to the machine at
+//!   that address (like the network in day 23).
+//!   A machine with no packets waiting for it receives `-1`.
+//!   Packets can also be sent to a `Nat`, which gets a chance to wake the
+//!   network up when it goes idle.
+//!
+//! Machines are scheduled round-robin: each one runs until it stops, waiting
+//! for input, before the next machine gets a turn.
+use super::stream::IntcodeInput;
+use super::{IntcodeError, Program, Stop, Word};
+use std::collections::VecDeque;
+use std::error;
+use std::fmt;
+use std::io;
+
+/// A number of intcode programs, wired together.
+pub struct Network<W = i64> {
+    machines: Vec<Program<W>>,
+    inboxes: Vec<VecDeque<W>>,
+    topology: Topology,
+
+    // Partially-written packets, for each machine.
+    partial: Vec<Vec<W>>,
+
+    // The address of the NAT, and the NAT itself.
+    nat: Option<(W, Box<dyn Nat<W>>)>,
+
+    // Output of the network as a whole.
+    output: Vec<W>,
+
+    // The last machine to halt.
+    last_halted: Option<usize>,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Topology {
+    Chain,
+    Ring,
+    Packets,
+}
+
+/// A packet sent between machines on a `Network` built with
+/// `Network::packets`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Packet<W = i64> {
+    pub dest: W,
+    pub x: W,
+    pub y: W,
+}
+
+/// A NAT monitors a packet network, and can wake it up when it goes idle.
+pub trait Nat<W = i64> {
+    /// Called with each packet sent to the NAT's address.
+    /// Returning `false` stops the network with `NetStop::Nat`.
+    fn receive(&mut self, packet: Packet<W>) -> bool;
+
+    /// Called when the network is idle: every machine is waiting for a
+    /// packet, and none are in flight.
+    /// The returned packet is delivered, and the network keeps running;
+    /// returning `None` stops the network with `NetStop::Idle`.
+    fn idle(&mut self) -> Option<Packet<W>>;
+}
+
+/// NetStop is the reason `Network::run` returned.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum NetStop<W = i64> {
+    /// Every machine has halted; `last` is the last one to do so.
+    /// A machine that ran into one of its limits counts as halted.
+    AllHalted { last: usize },
+
+    /// No machine can make any progress.
+    /// `waiting` holds each machine that is still waiting for input, and
+    /// `halted` holds each machine that has halted.
+    Deadlock {
+        waiting: Vec<usize>,
+        halted: Vec<usize>,
+    },
+
+    /// The packet network is idle, and there is no NAT to wake it up (or the
+    /// NAT decided not to).
+    Idle,
+
+    /// The NAT asked for the network to stop.
+    Nat,
+
+    /// A packet was sent to an address that does not exist.
+    /// `from` is the machine that sent it, or `None` if it came from the NAT.
+    Undeliverable {
+        from: Option<usize>,
+        packet: Packet<W>,
+    },
+}
+
+/// NetworkError is returned when one of the machines on a `Network` runs into
+/// an `IntcodeError`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct NetworkError {
+    /// The index of the faulting machine.
+    pub machine: usize,
+
+    /// The error the machine ran into.
+    pub error: IntcodeError,
+}
+
+impl fmt::Display for NetworkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "machine {}: {}", self.machine, self.error)
+    }
+}
+
+impl error::Error for NetworkError {}
+
+impl From<NetworkError> for io::Error {
+    fn from(e: NetworkError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, e)
+    }
+}
+
+impl<W: Word> Network<W> {
+    fn new(machines: Vec<Program<W>>, topology: Topology) -> Self {
+        let n = machines.len();
+        Self {
+            machines,
+            inboxes: vec![VecDeque::new(); n],
+            topology,
+            partial: vec![vec![]; n],
+            nat: None,
+            output: vec![],
+            last_halted: None,
+        }
+    }
+
+    /// Returns a network where the output of each machine is sent to the
+    /// next, and the output of the last machine is the output of the
+    /// network.
+    pub fn chain(machines: Vec<Program<W>>) -> Self {
+        Self::new(machines, Topology::Chain)
+    }
+
+    /// Returns a network like `chain`, except the output of the last machine
+    /// is also sent back to the first.
+    pub fn ring(machines: Vec<Program<W>>) -> Self {
+        Self::new(machines, Topology::Ring)
+    }
+
+    /// Returns a network where machines send each other packets.
+    ///
+    /// Each machine is given its index in `machines` as its address, which is
+    /// also the first input it receives.
+    pub fn packets(machines: Vec<Program<W>>) -> Self {
+        let mut network = Self::new(machines, Topology::Packets);
+        for (i, inbox) in network.inboxes.iter_mut().enumerate() {
+            inbox.push_back(W::from_i64(i as i64));
+        }
+        network
+    }
+
+    /// Attaches `nat` to the network, at `address`.
+    pub fn with_nat<N: Nat<W> + 'static>(mut self, address: W, nat: N) -> Self {
+        self.nat = Some((address, Box::new(nat)));
+        self
+    }
+
+    /// Queues `v` as input for the machine at index `machine`.
+    pub fn input(&mut self, machine: usize, v: W) -> &mut Self {
+        self.inboxes[machine].push_back(v);
+        self
+    }
+
+    /// Returns everything output by the last machine in a chain or ring.
+    pub fn output(&self) -> &[W] {
+        &self.output
+    }
+
+    /// Returns the number of machines on the network.
+    pub fn len(&self) -> usize {
+        self.machines.len()
+    }
+
+    /// Returns `true` if there are no machines on the network.
+    pub fn is_empty(&self) -> bool {
+        self.machines.is_empty()
+    }
+
+    /// Returns the machine at index `i`.
+    pub fn machine(&self, i: usize) -> &Program<W> {
+        &self.machines[i]
+    }
+
+    /// Returns the machine at index `i`.
+    pub fn machine_mut(&mut self, i: usize) -> &mut Program<W> {
+        &mut self.machines[i]
+    }
+
+    /// Returns `true` if the machine at index `i` has halted, or run into one
+    /// of its limits, so that it cannot go any further.
+    pub fn is_halted(&self, i: usize) -> bool {
+        match self.machines[i].reason_for_stop() {
+            None | Some(Stop::WaitingForInput) => false,
+            Some(_) => true,
+        }
+    }
+
+    /// Runs every machine on the network, round-robin, until they have all
+    /// halted, or the network can no longer make progress.
+    pub fn run(&mut self) -> Result<NetStop<W>, NetworkError> {
+        loop {
+            let progress = match self.topology {
+                Topology::Chain | Topology::Ring => self.round()?,
+                Topology::Packets => match self.packet_round()? {
+                    Ok(progress) => progress,
+                    Err(stop) => return Ok(stop),
+                },
+            };
+
+            let halted: Vec<usize> = (0..self.len()).filter(|&i| self.is_halted(i)).collect();
+            if !self.is_empty() && halted.len() == self.len() {
+                return Ok(NetStop::AllHalted {
+                    last: self.last_halted.unwrap_or(0),
+                });
+            }
+            if progress {
+                continue;
+            }
+
+            if self.topology == Topology::Packets {
+                match self.nat.as_mut().and_then(|(_, nat)| nat.idle()) {
+                    Some(packet) => {
+                        if let Some(stop) = self.deliver(None, packet) {
+                            return Ok(stop);
+                        }
+                        continue;
+                    }
+                    None => return Ok(NetStop::Idle),
+                }
+            }
+
+            let waiting = (0..self.len()).filter(|&i| !self.is_halted(i)).collect();
+            return Ok(NetStop::Deadlock { waiting, halted });
+        }
+    }
+
+    // Runs each machine in a chain or ring once, returning whether any of
+    // them made progress.
+    fn round(&mut self) -> Result<bool, NetworkError> {
+        let mut progress = false;
+        for i in 0..self.len() {
+            if self.is_halted(i) {
+                continue;
+            }
+
+            let pending = self.inboxes[i].len();
+            let ip = self.machines[i].ip();
+            let mut out: Vec<W> = vec![];
+            let stop = self.machines[i]
+                .run(&mut self.inboxes[i], &mut out)
+                .map_err(|error| NetworkError { machine: i, error })?;
+            if stop != Stop::WaitingForInput {
+                self.last_halted = Some(i);
+                progress = true;
+            }
+            if !out.is_empty() || self.inboxes[i].len() != pending || self.machines[i].ip() != ip {
+                progress = true;
+            }
+
+            if i + 1 < self.len() {
+                self.inboxes[i + 1].extend(out);
+                continue;
+            }
+            if self.topology == Topology::Ring {
+                self.inboxes[0].extend(out.iter().cloned());
+            }
+            self.output.extend(out);
+        }
+        Ok(progress)
+    }
+
+    // Runs each machine in a packet network once, returning whether any
+    // packets were sent or received, or why the network should stop.
+    fn packet_round(&mut self) -> Result<Result<bool, NetStop<W>>, NetworkError> {
+        let mut progress = false;
+        for i in 0..self.len() {
+            if self.is_halted(i) {
+                continue;
+            }
+            let pending = self.inboxes[i].len();
+            let mut out: Vec<W> = vec![];
+            let mut input = PacketInput {
+                inbox: &mut self.inboxes[i],
+                starved: false,
+            };
+            let stop = self.machines[i]
+                .run(&mut input, &mut out)
+                .map_err(|error| NetworkError { machine: i, error })?;
+            if stop != Stop::WaitingForInput {
+                self.last_halted = Some(i);
+                progress = true;
+            }
+            if self.inboxes[i].len() < pending {
+                progress = true;
+            }
+
+            self.partial[i].extend(out);
+            let n = self.partial[i].len() / 3 * 3;
+            let mut sent = self.partial[i].drain(..n).collect::<Vec<W>>().into_iter();
+            while let (Some(dest), Some(x), Some(y)) = (sent.next(), sent.next(), sent.next()) {
+                progress = true;
+                let packet = Packet { dest, x, y };
+                if let Some(stop) = self.deliver(Some(i), packet) {
+                    return Ok(Err(stop));
+                }
+            }
+        }
+        Ok(Ok(progress))
+    }
+
+    // Delivers `packet`, sent by machine `from`, returning a reason to stop
+    // the network if it could not be delivered, or the NAT wants to stop.
+    fn deliver(&mut self, from: Option<usize>, packet: Packet<W>) -> Option<NetStop<W>> {
+        if let Some((address, nat)) = &mut self.nat {
+            if packet.dest == *address {
+                if nat.receive(packet) {
+                    return None;
+                }
+                return Some(NetStop::Nat);
+            }
+        }
+        let dest = match packet.dest.to_usize() {
+            Some(dest) if dest < self.len() => dest,
+            _ => return Some(NetStop::Undeliverable { from, packet }),
+        };
+        let inbox = &mut self.inboxes[dest];
+        inbox.push_back(packet.x);
+        inbox.push_back(packet.y);
+        None
+    }
+}
+
+// Input for a machine on a packet network.
+// When the machine's inbox is empty, it is given a -1 the first time it asks
+// for input, and its turn ends the next time it asks.
+struct PacketInput<'a, W> {
+    inbox: &'a mut VecDeque<W>,
+    starved: bool,
+}
+
+impl<'a, W: Word> IntcodeInput<W> for PacketInput<'a, W> {
+    fn next_input(&mut self) -> Option<W> {
+        if let Some(v) = self.inbox.pop_front() {
+            return Some(v);
+        }
+        if self.starved {
+            return None;
+        }
+        self.starved = true;
+        Some(W::from_i64(-1))
+    }
+}
+
+#[cfg(test)]
+use super::assemble;
+#[cfg(test)]
+use std::{cell::RefCell, rc::Rc};
+
+#[cfg(test)]
+fn amplifiers(code: &str, phases: &[i64]) -> Vec<Program> {
+    phases
+        .iter()
+        .map(|&phase| {
+            let mut program = Program::from(code);
+            program.input(phase);
+            program
+        })
+        .collect()
+}
+
+#[test]
+fn test_chain() {
+    let code = "3,15,3,16,1002,16,10,16,1,16,15,15,4,15,99,0,0";
+    let mut network = Network::chain(amplifiers(code, &[4, 3, 2, 1, 0]));
+    network.input(0, 0);
+    assert_eq!(network.run().unwrap(), NetStop::AllHalted { last: 4 });
+    assert_eq!(network.output(), &[43210]);
+}
+
+#[test]
+fn test_ring() {
+    let code =
+        "3,26,1001,26,-4,26,3,27,1002,27,2,27,1,27,26,27,4,27,1001,28,-1,28,1005,28,6,99,0,0,5";
+    let mut network = Network::ring(amplifiers(code, &[9, 8, 7, 6, 5]));
+    network.input(0, 0);
+    assert_eq!(network.run().unwrap(), NetStop::AllHalted { last: 4 });
+    assert_eq!(network.output().last(), Some(&139629729));
+}
+
+#[test]
+fn test_deadlock() {
+    // The first machine never gets any input.
+    let code = "3,9,4,9,3,9,4,9,99,0";
+    let mut network = Network::chain(vec![Program::from(code), Program::from("99")]);
+    assert_eq!(
+        network.run().unwrap(),
+        NetStop::Deadlock {
+            waiting: vec![0],
+            halted: vec![1]
+        }
+    );
+    assert!(network.is_halted(1));
+}
+
+#[test]
+fn test_chain_limits() {
+    // A machine that runs into a limit is done, rather than waiting for
+    // input.
+    let mut limited = Program::from("3,9,4,9,3,9,4,9,99,0");
+    limited.set_instruction_limit(Some(0));
+    let waiting = Program::from("3,9,4,9,3,9,4,9,99,0");
+    let mut network = Network::chain(vec![limited, waiting]);
+    network.input(0, 1);
+    assert_eq!(
+        network.run().unwrap(),
+        NetStop::Deadlock {
+            waiting: vec![1],
+            halted: vec![0]
+        }
+    );
+    assert!(network.is_halted(0));
+
+    let mut limited = Program::from("3,9,4,9,3,9,4,9,99,0");
+    limited.set_instruction_limit(Some(0));
+    let mut network = Network::chain(vec![limited]);
+    assert_eq!(network.run().unwrap(), NetStop::AllHalted { last: 0 });
+}
+
+#[test]
+fn test_network_error() {
+    let mut network = Network::chain(vec![Program::from("99"), Program::from("42")]);
+    let err = network.run().unwrap_err();
+    assert_eq!(err.machine, 1);
+    assert_eq!(err.error, IntcodeError::UnknownOpcode { ip: 0, word: 42 });
+}
+
+// Machine 0 sends a packet to machine 1; each machine increments the X value
+// of any packet it receives, and passes it on to the next machine, with the
+// last machine sending it to the NAT at address 255.
+#[cfg(test)]
+const RELAY: &str = "
+            in -> [addr]
+            jt [addr], #loop
+            out #1
+            out #10
+            out #20
+    loop:   in -> [x]
+            eq [x], #-1 -> [tmp]
+            jt [tmp], #loop
+            in -> [y]
+            add [x], #1 -> [x]
+            add [addr], #1 -> [dest]
+            eq [dest], #3 -> [tmp]
+            jf [tmp], #send
+            add #255, #0 -> [dest]
+    send:   out [dest]
+            out [x]
+            out [y]
+            jt #1, #loop
+    addr:   data 0
+    x:      data 0
+    y:      data 0
+    dest:   data 0
+    tmp:    data 0
+";
+
+#[cfg(test)]
+struct TestNat {
+    received: Rc<RefCell<Vec<Packet>>>,
+    wakeups: usize,
+    stop_after: usize,
+}
+
+#[cfg(test)]
+impl Nat for TestNat {
+    fn receive(&mut self, packet: Packet) -> bool {
+        let mut received = self.received.borrow_mut();
+        received.push(packet);
+        received.len() < self.stop_after
+    }
+
+    fn idle(&mut self) -> Option<Packet> {
+        if self.wakeups == 0 {
+            return None;
+        }
+        self.wakeups -= 1;
+        let last = *self.received.borrow().last()?;
+        Some(Packet { dest: 0, ..last })
+    }
+}
+
+#[cfg(test)]
+fn relay_network(wakeups: usize, stop_after: usize) -> (Network, Rc<RefCell<Vec<Packet>>>) {
+    let mem = assemble(RELAY).unwrap();
+    let machines = (0..3).map(|_| Program::from(mem.clone())).collect();
+    let received = Rc::new(RefCell::new(vec![]));
+    let nat = TestNat {
+        received: received.clone(),
+        wakeups,
+        stop_after,
+    };
+    (Network::packets(machines).with_nat(255, nat), received)
+}
+
+#[test]
+fn test_packets_idle() {
+    let (mut network, received) = relay_network(1, 10);
+    assert_eq!(network.run().unwrap(), NetStop::Idle);
+    assert_eq!(
+        *received.borrow(),
+        vec![
+            Packet {
+                dest: 255,
+                x: 12,
+                y: 20
+            },
+            Packet {
+                dest: 255,
+                x: 15,
+                y: 20
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_packets_nat_stop() {
+    let (mut network, received) = relay_network(5, 1);
+    assert_eq!(network.run().unwrap(), NetStop::Nat);
+    assert_eq!(received.borrow().len(), 1);
+}
+
+#[test]
+fn test_packets_limits() {
+    // The machine never reads its address, which must not count as progress.
+    let mut limited = Program::from(assemble(RELAY).unwrap());
+    limited.set_instruction_limit(Some(0));
+    let mut network = Network::packets(vec![limited]);
+    assert_eq!(network.run().unwrap(), NetStop::AllHalted { last: 0 });
+
+    // Nor are packets sent to a machine that stopped at its limit, which it
+    // never reads.
+    let mut limited = Program::from(assemble(RELAY).unwrap());
+    limited.set_instruction_limit(Some(1));
+    let mut network = Network::packets(vec![limited, Program::from("104,0,104,1,104,2,99")]);
+    assert_eq!(network.run().unwrap(), NetStop::AllHalted { last: 1 });
+    assert_eq!(
+        network.machine(0).reason_for_stop(),
+        Some(Stop::InstructionLimit)
+    );
+}
+
+#[test]
+fn test_packets_undeliverable() {
+    let machines = vec![Program::from("104,7,104,1,104,2,99")];
+    let mut network = Network::packets(machines);
+    assert_eq!(
+        network.run().unwrap(),
+        NetStop::Undeliverable {
+            from: Some(0),
+            packet: Packet {
+                dest: 7,
+                x: 1,
+                y: 2
+            }
+        }
+    );
+}
@@ -0,0 +1,346 @@
+//! Saving a program's state to text, and loading it again.
+//!
+//! A save holds everything a `Snapshot` does, along with the memory the
+//! program was loaded with, so that a long-running session can be
+//! checkpointed, or the exact state of a misbehaving program attached to a bug
+//! report.
+//! Each line is a field name followed by its value:
+//!
+//! ```text
+//! intcode-save 1
+//! ip 4
+//! rel_base 1
+//! reason waiting-for-input
+//! executed 2
+//! outputs 1
+//! input 42,7
+//! initial 109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99
+//! len 101
+//! mem 100:1
+//! ```
+//!
+//! The first line names the format and its version, which is bumped whenever
+//! the format changes.
+//! Rather than the whole of the program's memory, `mem` lists each address
+//! whose value differs from the initial memory, which is usually a small part
+//! of it, and `len` records how far memory has grown.
+//!
+//! As with snapshots, the program's breakpoints, watchpoints, limits, and
+//! dialect are not saved.
+use super::{Program, Stop, Word, MAX_MEMORY};
+use std::error;
+use std::fmt;
+use std::io;
+
+/// The first line of every save.
+const HEADER: &str = "intcode-save";
+
+/// The version of the format written by `Program::save`.
+const VERSION: u32 = 1;
+
+const STOPS: [(Stop, &str); 5] = [
+    (Stop::WaitingForInput, "waiting-for-input"),
+    (Stop::HCF, "halted"),
+    (Stop::MemoryLimit, "memory-limit"),
+    (Stop::InstructionLimit, "instruction-limit"),
+    (Stop::OutputLimit, "output-limit"),
+];
+
+/// LoadError is returned by `Program::load` when its input is not a valid
+/// save.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum LoadError {
+    /// The input does not start with the save header.
+    NotASave,
+
+    /// The save was written in a version of the format that cannot be read.
+    UnsupportedVersion(u32),
+
+    /// The 1-based line `line` could not be parsed.
+    BadLine { line: usize, text: String },
+
+    /// The save is missing a field.
+    Missing(&'static str),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotASave => write!(f, "not an intcode save"),
+            Self::UnsupportedVersion(v) => write!(f, "unsupported save version {}", v),
+            Self::BadLine { line, text } => write!(f, "line {}: cannot parse {:?}", line, text),
+            Self::Missing(field) => write!(f, "missing field {:?}", field),
+        }
+    }
+}
+
+impl error::Error for LoadError {}
+
+impl From<LoadError> for io::Error {
+    fn from(e: LoadError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, e)
+    }
+}
+
+impl<W: Word> Program<W> {
+    /// Returns the program's current state, in a form that can be turned
+    /// back into a program with `Program::load`.
+    pub fn save(&self) -> String {
+        let mem = self.mem.as_slice();
+        let changed = (0..mem.len()).filter_map(|i| {
+            let initial = self
+                .initial
+                .get(i)
+                .cloned()
+                .unwrap_or_else(|| W::from_i64(0));
+            if mem[i] == initial {
+                return None;
+            }
+            Some(format!("{}:{}", i, mem[i]))
+        });
+        let reason = match self.reason {
+            Some(stop) => STOPS.iter().find(|(s, _)| *s == stop).unwrap().1,
+            None => "none",
+        };
+
+        let fields = [
+            ("ip", self.ip.to_string()),
+            ("rel_base", self.rel_base.to_string()),
+            ("reason", reason.to_string()),
+            ("executed", self.executed.to_string()),
+            ("outputs", self.outputs.to_string()),
+            ("input", join(self.input.iter())),
+            ("initial", join(self.initial.iter())),
+            ("len", mem.len().to_string()),
+            ("mem", join(changed)),
+        ];
+        let mut s = format!("{} {}\n", HEADER, VERSION);
+        for (name, value) in &fields {
+            s += name;
+            if !value.is_empty() {
+                s += " ";
+                s += value;
+            }
+            s += "\n";
+        }
+        s
+    }
+
+    /// Loads a program from `s`, which was returned by `Program::save`.
+    ///
+    /// The program is loaded with `initial` as its initial memory, so that
+    /// `reset_to_initial` still resets it to the start of the program, and
+    /// `Backend::Flat` memory.
+    /// A `len` past `MAX_MEMORY`, or a `mem` address that is not below `len`,
+    /// is a `LoadError::BadLine`, rather than memory being grown to fit it.
+    pub fn load(s: &str) -> Result<Self, LoadError> {
+        let mut lines = s.lines().enumerate();
+        let version = match lines.next().and_then(|(_, l)| l.split_once(' ')) {
+            Some((HEADER, version)) => version.parse().map_err(|_| LoadError::BadLine {
+                line: 1,
+                text: version.to_string(),
+            })?,
+            _ => return Err(LoadError::NotASave),
+        };
+        if version != VERSION {
+            return Err(LoadError::UnsupportedVersion(version));
+        }
+
+        let mut fields = Fields::default();
+        for (i, line) in lines {
+            let bad = || LoadError::BadLine {
+                line: i + 1,
+                text: line.to_string(),
+            };
+            let (name, value) = line.split_once(' ').unwrap_or((line, ""));
+            match name {
+                "ip" => fields.ip = Some(value.parse().map_err(|_| bad())?),
+                "rel_base" => fields.rel_base = Some(value.parse().map_err(|_| bad())?),
+                "reason" => {
+                    let stop = STOPS
+                        .iter()
+                        .find(|(_, s)| *s == value)
+                        .map(|(stop, _)| *stop);
+                    if stop.is_none() && value != "none" {
+                        return Err(bad());
+                    }
+                    fields.reason = Some(stop);
+                }
+                "executed" => fields.executed = Some(value.parse().map_err(|_| bad())?),
+                "outputs" => fields.outputs = Some(value.parse().map_err(|_| bad())?),
+                "input" => fields.input = Some(parse_list(value).ok_or_else(bad)?),
+                "initial" => fields.initial = Some(parse_list(value).ok_or_else(bad)?),
+                "len" => {
+                    let len = value.parse().map_err(|_| bad())?;
+                    if len > MAX_MEMORY {
+                        return Err(bad());
+                    }
+                    fields.len = Some(len);
+                }
+                "mem" => {
+                    let mem = split_list(value)
+                        .map(|cell| {
+                            let (address, v) = cell.split_once(':')?;
+                            Some((address.parse().ok()?, v.parse().ok()?))
+                        })
+                        .collect::<Option<_>>();
+                    fields.mem = Some((i, mem.ok_or_else(bad)?));
+                }
+                "" => {}
+                _ => return Err(bad()),
+            }
+        }
+
+        let initial = fields.initial.ok_or(LoadError::Missing("initial"))?;
+        let len = fields.len.ok_or(LoadError::Missing("len"))?;
+        let (i, mem) = fields.mem.ok_or(LoadError::Missing("mem"))?;
+        if mem.iter().any(|(address, _)| *address >= len) {
+            return Err(LoadError::BadLine {
+                line: i + 1,
+                text: s.lines().nth(i).unwrap_or_default().to_string(),
+            });
+        }
+        let mut program = Program::from(initial);
+        for (address, v) in mem {
+            program.set_mem(address, v);
+        }
+        program.mem.grow(len);
+        program.ip = fields.ip.ok_or(LoadError::Missing("ip"))?;
+        program.rel_base = fields.rel_base.ok_or(LoadError::Missing("rel_base"))?;
+        program.reason = fields.reason.ok_or(LoadError::Missing("reason"))?;
+        program.executed = fields.executed.ok_or(LoadError::Missing("executed"))?;
+        program.outputs = fields.outputs.ok_or(LoadError::Missing("outputs"))?;
+        program.input = fields.input.ok_or(LoadError::Missing("input"))?.into();
+        Ok(program)
+    }
+}
+
+// The fields of a save, as they are parsed.
+struct Fields<W> {
+    ip: Option<usize>,
+    rel_base: Option<W>,
+    reason: Option<Option<Stop>>,
+    executed: Option<u64>,
+    outputs: Option<u64>,
+    input: Option<Vec<W>>,
+    initial: Option<Vec<W>>,
+    len: Option<usize>,
+    // The changed memory, along with the 0-based line it was read from.
+    mem: Option<(usize, Vec<(usize, W)>)>,
+}
+
+impl<W> Default for Fields<W> {
+    fn default() -> Self {
+        Self {
+            ip: None,
+            rel_base: None,
+            reason: None,
+            executed: None,
+            outputs: None,
+            input: None,
+            initial: None,
+            len: None,
+            mem: None,
+        }
+    }
+}
+
+fn join<T: fmt::Display>(v: impl Iterator<Item = T>) -> String {
+    v.map(|x| x.to_string()).collect::<Vec<_>>().join(",")
+}
+
+// Splits a comma-separated list, which may be empty.
+fn split_list(s: &str) -> impl Iterator<Item = &str> {
+    s.split(',').filter(|v| !v.is_empty())
+}
+
+fn parse_list<W: Word>(s: &str) -> Option<Vec<W>> {
+    split_list(s).map(|v| v.parse().ok()).collect()
+}
+
+#[test]
+fn test_save_load() {
+    let code = "3,20,1001,20,5,21,4,21,3,20,1105,1,0,99";
+    let mut program = Program::from(code);
+    program.input(2);
+    assert_eq!(program.execute().unwrap(), vec![7]);
+    assert_eq!(program.reason_for_stop(), Some(Stop::WaitingForInput));
+    program.input(5).input(6);
+    program.set_mem(500, 1);
+
+    let saved = program.save();
+    assert!(saved.starts_with("intcode-save 1\n"));
+    assert!(saved.contains("\nmem 20:2,21:7,500:1\n"));
+    let mut loaded = Program::<i64>::load(&saved).unwrap();
+    assert_eq!(loaded.save(), saved);
+    assert_eq!(loaded.ip(), program.ip());
+    assert_eq!(loaded.reason_for_stop(), Some(Stop::WaitingForInput));
+    assert_eq!(loaded.instruction_count(), program.instruction_count());
+    assert_eq!(loaded.mem.as_slice(), program.mem.as_slice());
+    assert_eq!(loaded.execute().unwrap(), program.execute().unwrap());
+
+    // The loaded program remembers where it started.
+    loaded.reset_to_initial();
+    assert_eq!(loaded.save(), Program::from(code).save());
+
+    // Words other than i64 round-trip too.
+    let mut program: Program<num::BigInt> = "104,1125899906842624,3,0,99".parse().unwrap();
+    program.execute().unwrap();
+    let loaded = Program::<num::BigInt>::load(&program.save()).unwrap();
+    assert_eq!(loaded.save(), program.save());
+}
+
+#[test]
+fn test_load_errors() {
+    let saved = Program::from("99").save();
+    let err = Program::<i64>::load("99").err();
+    assert_eq!(err, Some(LoadError::NotASave));
+
+    let v2 = saved.replace("intcode-save 1", "intcode-save 2");
+    let err = Program::<i64>::load(&v2).err();
+    assert_eq!(err, Some(LoadError::UnsupportedVersion(2)));
+
+    let bad = saved.replace("ip 0", "ip x");
+    let err = Program::<i64>::load(&bad).err();
+    assert_eq!(
+        err,
+        Some(LoadError::BadLine {
+            line: 2,
+            text: "ip x".to_string()
+        })
+    );
+
+    let missing = saved.replace("len 1\n", "");
+    let err = Program::<i64>::load(&missing).err();
+    assert_eq!(err, Some(LoadError::Missing("len")));
+
+    // Memory is never grown past `MAX_MEMORY`, or past `len`, to load a
+    // save.
+    let huge = saved.replace("len 1", "len 18446744073709551615");
+    let err = Program::<i64>::load(&huge).err();
+    assert_eq!(
+        err,
+        Some(LoadError::BadLine {
+            line: 9,
+            text: "len 18446744073709551615".to_string()
+        })
+    );
+    let outside = saved.replace("mem\n", "mem 99999999999:1\n");
+    let err = Program::<i64>::load(&outside).err();
+    assert_eq!(
+        err,
+        Some(LoadError::BadLine {
+            line: 10,
+            text: "mem 99999999999:1".to_string()
+        })
+    );
+    let past_len = saved.replace("mem\n", "mem 0:1,1:1\n");
+    let err = Program::<i64>::load(&past_len).err();
+    assert_eq!(
+        err,
+        Some(LoadError::BadLine {
+            line: 10,
+            text: "mem 0:1,1:1".to_string()
+        })
+    );
+}
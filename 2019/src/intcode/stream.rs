@@ -0,0 +1,180 @@
+//! Pluggable input and output for intcode programs.
+//!
+//! `Program::run` pulls its input from an `IntcodeInput`, one value at a time
+//! and only when an input instruction needs one, and pushes each output value
+//! to an `IntcodeOutput` as soon as it is produced.
+//! This lets a driver react to each output as it happens, rather than
+//! collecting all of the program's output into a `Vec` first.
+use std::collections::VecDeque;
+use std::sync::mpsc::{Receiver, Sender};
+
+/// A source of input values for an intcode program.
+pub trait IntcodeInput<W = i64> {
+    /// Returns the next input value, or `None` if there is no input available
+    /// right now.
+    ///
+    /// Returning `None` stops the program with `Stop::WaitingForInput`; it
+    /// can be resumed once more input is available.
+    fn next_input(&mut self) -> Option<W>;
+}
+
+/// A sink for the output values of an intcode program.
+pub trait IntcodeOutput<W = i64> {
+    /// Receives a single output value.
+    fn push_output(&mut self, v: W);
+}
+
+impl<W> IntcodeInput<W> for VecDeque<W> {
+    fn next_input(&mut self) -> Option<W> {
+        self.pop_front()
+    }
+}
+
+impl<W> IntcodeOutput<W> for VecDeque<W> {
+    fn push_output(&mut self, v: W) {
+        self.push_back(v);
+    }
+}
+
+impl<W> IntcodeOutput<W> for Vec<W> {
+    fn push_output(&mut self, v: W) {
+        self.push(v);
+    }
+}
+
+/// Input is received from the channel without blocking, so the program stops
+/// with `Stop::WaitingForInput` whenever the channel is empty, or once the
+/// sending half has hung up.
+impl<W> IntcodeInput<W> for Receiver<W> {
+    fn next_input(&mut self) -> Option<W> {
+        self.try_recv().ok()
+    }
+}
+
+/// Blocking wraps the receiving half of a channel, so that the program waits
+/// for input to arrive, rather than stopping.
+///
+/// Once the sending half of the channel has hung up, and the channel is
+/// empty, the program stops with `Stop::WaitingForInput`.
+pub struct Blocking<W = i64>(pub Receiver<W>);
+
+impl<W> IntcodeInput<W> for Blocking<W> {
+    fn next_input(&mut self) -> Option<W> {
+        self.0.recv().ok()
+    }
+}
+
+/// Output sent after the receiving half of the channel has hung up is
+/// discarded.
+impl<W> IntcodeOutput<W> for Sender<W> {
+    fn push_output(&mut self, v: W) {
+        let _ = self.send(v);
+    }
+}
+
+/// InputFn turns a closure into an `IntcodeInput`.
+///
+/// The closure is called each time the program needs an input value.
+pub struct InputFn<F>(pub F);
+
+impl<W, F: FnMut() -> Option<W>> IntcodeInput<W> for InputFn<F> {
+    fn next_input(&mut self) -> Option<W> {
+        (self.0)()
+    }
+}
+
+/// OutputFn turns a closure into an `IntcodeOutput`.
+///
+/// The closure is called with each value the program outputs.
+pub struct OutputFn<F>(pub F);
+
+impl<W, F: FnMut(W)> IntcodeOutput<W> for OutputFn<F> {
+    fn push_output(&mut self, v: W) {
+        (self.0)(v)
+    }
+}
+
+// An input that never has anything to give.
+pub(super) struct NoInput;
+
+impl<W> IntcodeInput<W> for NoInput {
+    fn next_input(&mut self) -> Option<W> {
+        None
+    }
+}
+
+#[cfg(test)]
+use super::{assemble, Program, Stop};
+
+// Reads numbers, and outputs each one doubled, until it reads a zero.
+#[cfg(test)]
+const DOUBLER: &str = "
+    loop:   in -> [n]
+            jf [n], #done
+            mul [n], #2 -> [n]
+            out [n]
+            jt #1, #loop
+    done:   hlt
+    n:      data 0
+";
+
+#[test]
+fn test_run_vecdeque() {
+    let mut program = Program::from(assemble(DOUBLER).unwrap());
+    let mut input: VecDeque<i64> = vec![1, 2, 3].into_iter().collect();
+    let mut output: VecDeque<i64> = VecDeque::new();
+
+    let stop = program.run(&mut input, &mut output).unwrap();
+    assert_eq!(stop, Stop::WaitingForInput);
+    assert_eq!(output, vec![2, 4, 6]);
+
+    input.push_back(0);
+    let stop = program.run(&mut input, &mut output).unwrap();
+    assert_eq!(stop, Stop::HCF);
+    assert_eq!(output.len(), 3);
+}
+
+#[test]
+fn test_run_closures() {
+    // Input queued with `Program::input` is used up first.
+    let mut program = Program::from(assemble(DOUBLER).unwrap());
+    let mut output: Vec<i64> = vec![];
+    program.input(3);
+    let stop = program.run(&mut InputFn(|| Some(0)), &mut output).unwrap();
+    assert_eq!(stop, Stop::HCF);
+    assert_eq!(output, vec![6]);
+
+    // Feed each output back in as the next input, until it gets too big.
+    let mut program = Program::from(assemble(DOUBLER).unwrap());
+    let feedback = std::cell::Cell::new(1);
+    let stop = program
+        .run(
+            &mut InputFn(|| match feedback.get() {
+                n if n > 100 => Some(0),
+                n => Some(n),
+            }),
+            &mut OutputFn(|v| feedback.set(v)),
+        )
+        .unwrap();
+    assert_eq!(stop, Stop::HCF);
+    assert_eq!(feedback.get(), 128);
+}
+
+#[test]
+fn test_run_channels() {
+    use std::sync::mpsc::channel;
+
+    let (input_tx, mut input_rx) = channel();
+    let (mut output_tx, output_rx) = channel();
+    let mut program = Program::from(assemble(DOUBLER).unwrap());
+
+    input_tx.send(5).unwrap();
+    input_tx.send(7).unwrap();
+    let stop = program.run(&mut input_rx, &mut output_tx).unwrap();
+    assert_eq!(stop, Stop::WaitingForInput);
+    assert_eq!(output_rx.try_iter().collect::<Vec<i64>>(), vec![10, 14]);
+
+    drop(input_tx);
+    let stop = program.run(&mut input_rx, &mut output_tx).unwrap();
+    assert_eq!(stop, Stop::WaitingForInput);
+}
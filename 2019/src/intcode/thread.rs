@@ -0,0 +1,118 @@
+//! Run intcode programs on their own threads.
+//!
+//! A spawned program reads its input from, and writes its output to,
+//! `std::sync::mpsc` channels, blocking while it waits for input.
+//! Spawned programs can be wired together by handing the receiving half of
+//! one program's output channel to another program as its input.
+use super::stream::Blocking;
+use super::{IntcodeError, Program, Stop, Word};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::thread::{self, JoinHandle};
+
+// The join handle of a spawned program.
+type Handle<W> = JoinHandle<Result<Finished<W>, IntcodeError>>;
+
+/// Finished is returned by the join handle of a spawned program, once the
+/// program has stopped.
+pub struct Finished<W = i64> {
+    /// The program, with its final memory.
+    pub program: Program<W>,
+
+    /// The reason the program stopped.
+    pub stop: Stop,
+}
+
+impl<W: Word> Program<W> {
+    /// Runs the program on its own thread, reading input from `input`, and
+    /// sending its output to `output`.
+    ///
+    /// The program runs until it halts, or it needs more input after every
+    /// `Sender` for `input` has been dropped.
+    pub fn spawn(mut self, input: Receiver<W>, mut output: Sender<W>) -> Handle<W> {
+        thread::spawn(move || {
+            let stop = self.run(&mut Blocking(input), &mut output)?;
+            Ok(Finished {
+                program: self,
+                stop,
+            })
+        })
+    }
+
+    /// Runs the program on its own thread, like `spawn`, creating the input
+    /// and output channels for it.
+    ///
+    /// Returns the sending half of the program's input channel, and the
+    /// receiving half of its output channel, along with the join handle.
+    pub fn spawn_with_channels(self) -> (Sender<W>, Receiver<W>, Handle<W>) {
+        let (input_tx, input_rx) = channel();
+        let (output_tx, output_rx) = channel();
+        let handle = self.spawn(input_rx, output_tx);
+        (input_tx, output_rx, handle)
+    }
+}
+
+#[test]
+fn test_spawn_with_channels() {
+    let code = "3,9,8,9,10,9,4,9,99,-1,8";
+    let (input, output, handle) = Program::from(code).spawn_with_channels();
+    input.send(8).unwrap();
+    assert_eq!(output.recv().unwrap(), 1);
+
+    let finished = handle.join().unwrap().unwrap();
+    assert_eq!(finished.stop, Stop::HCF);
+    assert_eq!(finished.program.peek_mem(9), 1);
+}
+
+#[test]
+fn test_spawn_hang_up() {
+    // Echoes its input until it is told to stop.
+    let code = "3,7,4,7,1105,1,0,0";
+    let (input, output, handle) = Program::from(code).spawn_with_channels();
+    for n in 1..=3 {
+        input.send(n).unwrap();
+        assert_eq!(output.recv().unwrap(), n);
+    }
+    drop(input);
+
+    let finished = handle.join().unwrap().unwrap();
+    assert_eq!(finished.stop, Stop::WaitingForInput);
+    assert!(output.recv().is_err());
+}
+
+#[test]
+fn test_spawn_error() {
+    let (_input, _output, handle) = Program::from("42").spawn_with_channels();
+    let err = handle.join().unwrap().err().unwrap();
+    assert_eq!(err, IntcodeError::UnknownOpcode { ip: 0, word: 42 });
+}
+
+#[test]
+fn test_spawn_feedback_loop() {
+    // Day 7's amplifiers, each on its own thread.
+    let code =
+        "3,26,1001,26,-4,26,3,27,1002,27,2,27,1,27,26,27,4,27,1001,28,-1,28,1005,28,6,99,0,0,5";
+
+    let (first, mut rx) = channel();
+    let mut handles = vec![];
+    for &phase in &[9, 8, 7, 6, 5] {
+        let mut program = Program::from(code);
+        program.input(phase);
+        let (tx, next) = channel();
+        handles.push(program.spawn(rx, tx));
+        rx = next;
+    }
+
+    // Feed the output of the last amplifier back to the first, keeping track
+    // of the last signal, until the last amplifier halts.
+    first.send(0).unwrap();
+    let mut signal = 0;
+    for v in rx {
+        signal = v;
+        let _ = first.send(v);
+    }
+    assert_eq!(signal, 139629729);
+
+    for handle in handles {
+        assert_eq!(handle.join().unwrap().unwrap().stop, Stop::HCF);
+    }
+}
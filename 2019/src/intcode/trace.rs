@@ -0,0 +1,562 @@
+//! Tracing and profiling of intcode programs.
+//!
+//! A `Tracer` attached to a `Program` with `Program::set_tracer` is handed an
+//! `Event` for every instruction the program executes, describing the decoded
+//! instruction, the operand values it read, and any memory it wrote.
+//!
+//! Two tracers are provided: `TraceWriter` writes each event as a line of
+//! text, and `Profile` counts how often each instruction is executed, which
+//! loops are taken, and which memory addresses are read and written.
+//!
+//! A program's tracer is not cloned along with it, so to look at a tracer
+//! after the program has run, attach an `Arc<Mutex<T>>` and keep a clone of
+//! it:
+//!
+//! ```ignore
+//! let profile = Arc::new(Mutex::new(Profile::new()));
+//! program.set_tracer(profile.clone());
+//! program.execute()?;
+//! println!("{}", profile.lock().unwrap());
+//! ```
+use super::disasm::Kind;
+use super::{Instruction, IntcodeError, IntcodeInput, Line, Parameter, Program, Step, Stop, Word};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufWriter};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+/// A Tracer is handed an `Event` for each instruction a program executes.
+pub trait Tracer<W = i64>: Send {
+    fn trace(&mut self, event: &Event<W>);
+}
+
+impl<W, T: Tracer<W>> Tracer<W> for Arc<Mutex<T>> {
+    fn trace(&mut self, event: &Event<W>) {
+        if let Ok(mut t) = self.lock() {
+            t.trace(event);
+        }
+    }
+}
+
+/// A pair of tracers are both handed each event, in order.
+impl<W, A: Tracer<W>, B: Tracer<W>> Tracer<W> for (A, B) {
+    fn trace(&mut self, event: &Event<W>) {
+        self.0.trace(event);
+        self.1.trace(event);
+    }
+}
+
+// Holds a program's tracer, if it has one.
+// Clones of a program start out without a tracer.
+pub(super) struct Hook<W>(Option<Box<dyn Tracer<W>>>);
+
+impl<W> Hook<W> {
+    pub(super) fn is_set(&self) -> bool {
+        self.0.is_some()
+    }
+}
+
+impl<W> Default for Hook<W> {
+    fn default() -> Self {
+        Self(None)
+    }
+}
+
+impl<W> Clone for Hook<W> {
+    fn clone(&self) -> Self {
+        Self(None)
+    }
+}
+
+impl<W: Word> Program<W> {
+    /// Attaches `tracer` to the program, replacing any tracer it already
+    /// had.
+    pub fn set_tracer<T: Tracer<W> + 'static>(&mut self, tracer: T) -> &mut Self {
+        self.tracer = Hook(Some(Box::new(tracer)));
+        self
+    }
+
+    /// Detaches and returns the program's tracer.
+    pub fn take_tracer(&mut self) -> Option<Box<dyn Tracer<W>>> {
+        self.tracer.0.take()
+    }
+
+    // Executes `inst`, like `execute_instruction`, and hands an `Event`
+    // describing it to the program's tracer.
+    pub(super) fn step_traced<I>(
+        &mut self,
+        inst: Instruction<W>,
+        next: usize,
+        input: &mut I,
+    ) -> Result<Step<W>, IntcodeError>
+    where
+        I: IntcodeInput<W> + ?Sized,
+    {
+        let (reads, write) = inst.operands();
+        let mut event = Event {
+            ip: self.ip,
+            rel_base: self.rel_base.clone(),
+            line: Line {
+                address: self.ip,
+                kind: Kind::Instruction(inst.clone()),
+            },
+            operands: [None, None],
+            write: None,
+            next: self.ip,
+        };
+        for (operand, param) in event.operands.iter_mut().zip(reads) {
+            *operand = param.and_then(|p| self.peek_operand(p));
+        }
+        let target = write.and_then(|p| self.resolve_out_ptr(p).ok());
+        let old = target.map(|address| self.peek_mem(address));
+
+        let step = self.execute_instruction(inst, next, input)?;
+        if step == Step::Stopped(Stop::WaitingForInput) {
+            return Ok(step);
+        }
+
+        event.write = target.zip(old).map(|(address, old)| MemoryWrite {
+            address,
+            old,
+            new: self.peek_mem(address),
+        });
+        event.next = self.ip;
+        if let Some(tracer) = self.tracer.0.as_mut() {
+            tracer.trace(&event);
+        }
+        Ok(step)
+    }
+
+    // Returns the operand `param` resolves to, without growing memory, or
+    // `None` if it does not resolve to an address that can be used.
+    // Relative addresses are resolved as they are when the instruction is
+    // executed, so they never wrap around.
+    fn peek_operand(&self, param: &Parameter<W>) -> Option<Operand<W>> {
+        match *param {
+            Parameter::Immediate(ref value) => Some(Operand::Immediate(value.clone())),
+            Parameter::Positional(address) => Some(Operand::Memory {
+                address,
+                value: self.peek_mem(address),
+            }),
+            Parameter::Relative(ref n) => {
+                let address = self.relative_address(n).ok()?;
+                Some(Operand::Memory {
+                    address,
+                    value: self.peek_mem(address),
+                })
+            }
+        }
+    }
+}
+
+impl<W> Instruction<W> {
+    fn is_jump(&self) -> bool {
+        matches!(self, Self::JumpIfTrue(..) | Self::JumpIfFalse(..))
+    }
+}
+
+/// Event describes a single executed instruction.
+///
+/// Events are displayed as a single line, with four fields separated by
+/// `" ; "`: the disassembled instruction, the relative base, the operands
+/// the instruction read, and the memory it wrote, if any.
+/// For example:
+///
+/// ```text
+/// 0004: ADD [9], #-1 -> [9] ; rb=0 ; [9]=3 #-1 ; [9]=3->2
+/// ```
+pub struct Event<W = i64> {
+    ip: usize,
+    rel_base: W,
+    line: Line<W>,
+    operands: [Option<Operand<W>>; 2],
+    write: Option<MemoryWrite<W>>,
+    next: usize,
+}
+
+impl<W> Event<W> {
+    /// Returns the address of the instruction.
+    pub fn ip(&self) -> usize {
+        self.ip
+    }
+
+    /// Returns the relative base the instruction was executed with.
+    pub fn rel_base(&self) -> &W {
+        &self.rel_base
+    }
+
+    /// Returns the decoded instruction.
+    pub fn instruction(&self) -> &Line<W> {
+        &self.line
+    }
+
+    /// Returns the operands the instruction read, in order.
+    pub fn operands(&self) -> impl Iterator<Item = &Operand<W>> {
+        self.operands.iter().flatten()
+    }
+
+    /// Returns the memory write made by the instruction, if any.
+    pub fn write(&self) -> Option<&MemoryWrite<W>> {
+        self.write.as_ref()
+    }
+
+    /// Returns the instruction pointer after the instruction was executed.
+    pub fn next_ip(&self) -> usize {
+        self.next
+    }
+
+    fn instruction_kind(&self) -> Option<&Instruction<W>> {
+        match &self.line.kind {
+            Kind::Instruction(inst) => Some(inst),
+            Kind::Data(_) => None,
+        }
+    }
+}
+
+impl<W: Word> fmt::Display for Event<W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ; rb={} ;", self.line, self.rel_base)?;
+        let mut operands = self.operands().peekable();
+        if operands.peek().is_none() {
+            write!(f, " -")?;
+        }
+        for operand in operands {
+            write!(f, " {}", operand)?;
+        }
+        match &self.write {
+            Some(w) => write!(f, " ; [{}]={}->{}", w.address, w.old, w.new),
+            None => write!(f, " ; -"),
+        }
+    }
+}
+
+/// Operand is a value read by an instruction.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Operand<W = i64> {
+    /// A value given in the instruction itself.
+    Immediate(W),
+
+    /// A value read from memory.
+    Memory { address: usize, value: W },
+}
+
+impl<W> Operand<W> {
+    /// Returns the value of the operand.
+    pub fn value(&self) -> &W {
+        match self {
+            Self::Immediate(value) | Self::Memory { value, .. } => value,
+        }
+    }
+}
+
+impl<W: Word> fmt::Display for Operand<W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Immediate(value) => write!(f, "#{}", value),
+            Self::Memory { address, value } => write!(f, "[{}]={}", address, value),
+        }
+    }
+}
+
+/// MemoryWrite holds the details of a write to memory made by an instruction.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct MemoryWrite<W = i64> {
+    /// The memory address that was written to.
+    pub address: usize,
+
+    /// The value held at `address` before the write.
+    pub old: W,
+
+    /// The value written to `address`.
+    pub new: W,
+}
+
+/// TraceWriter writes each event to `T`, one line per event.
+///
+/// Since `Tracer::trace` cannot fail, the first error returned by `T` is
+/// held onto, and nothing more is written; `finish` returns it.
+pub struct TraceWriter<T: io::Write> {
+    w: T,
+    err: Option<io::Error>,
+}
+
+impl TraceWriter<BufWriter<File>> {
+    /// Creates a file at `path`, and returns a TraceWriter that writes to it.
+    pub fn create<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Ok(Self::new(BufWriter::new(File::create(path)?)))
+    }
+}
+
+impl<T: io::Write> TraceWriter<T> {
+    pub fn new(w: T) -> Self {
+        Self { w, err: None }
+    }
+
+    /// Flushes the writer, and returns it, or the first error it returned.
+    pub fn finish(mut self) -> io::Result<T> {
+        if let Some(e) = self.err.take() {
+            return Err(e);
+        }
+        self.w.flush()?;
+        Ok(self.w)
+    }
+}
+
+impl<W: Word, T: io::Write + Send> Tracer<W> for TraceWriter<T> {
+    fn trace(&mut self, event: &Event<W>) {
+        if self.err.is_some() {
+            return;
+        }
+        if let Err(e) = writeln!(self.w, "{}", event) {
+            self.err = Some(e);
+        }
+    }
+}
+
+/// Profile counts how often each instruction is executed, how often each
+/// loop is taken, and how often each memory address is read and written.
+///
+/// A loop is identified by a jump back to an earlier address; each time the
+/// jump is taken counts as one iteration of the loop.
+///
+/// Displaying a Profile gives a summary of the ten hottest entries in each
+/// category.
+#[derive(Clone)]
+pub struct Profile<W = i64> {
+    total: u64,
+
+    // Execution counts, along with the instruction first executed at each
+    // address.
+    executed: HashMap<usize, (u64, Instruction<W>)>,
+
+    // Keyed by (start, end), where `end` is the address of the jump.
+    loops: HashMap<(usize, usize), u64>,
+
+    reads: HashMap<usize, u64>,
+    writes: HashMap<usize, u64>,
+}
+
+/// Loop is a span of instructions repeated by a jump at its end.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Loop {
+    /// The address jumped back to.
+    pub start: usize,
+
+    /// The address of the jump instruction.
+    pub end: usize,
+
+    /// The number of times the jump was taken.
+    pub iterations: u64,
+}
+
+impl<W> Default for Profile<W> {
+    fn default() -> Self {
+        Self {
+            total: 0,
+            executed: HashMap::new(),
+            loops: HashMap::new(),
+            reads: HashMap::new(),
+            writes: HashMap::new(),
+        }
+    }
+}
+
+impl<W> Profile<W> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the total number of instructions executed.
+    pub fn instructions(&self) -> u64 {
+        self.total
+    }
+
+    /// Returns the number of times the instruction at `address` was
+    /// executed.
+    pub fn executions(&self, address: usize) -> u64 {
+        self.executed.get(&address).map_or(0, |(n, _)| *n)
+    }
+
+    /// Returns the `n` most executed instruction addresses, and their
+    /// execution counts, most executed first.
+    pub fn hottest(&self, n: usize) -> Vec<(usize, u64)> {
+        top(self.executed.iter().map(|(&a, &(c, _))| (a, c)), n)
+    }
+
+    /// Returns the `n` loops with the most iterations, most iterations
+    /// first.
+    pub fn hot_loops(&self, n: usize) -> Vec<Loop> {
+        let mut loops: Vec<Loop> = self
+            .loops
+            .iter()
+            .map(|(&(start, end), &iterations)| Loop {
+                start,
+                end,
+                iterations,
+            })
+            .collect();
+        loops.sort_by_key(|l| (std::cmp::Reverse(l.iterations), l.start, l.end));
+        loops.truncate(n);
+        loops
+    }
+
+    /// Returns the `n` most read memory addresses, and how many times each
+    /// was read, most read first.
+    pub fn most_read(&self, n: usize) -> Vec<(usize, u64)> {
+        top(self.reads.iter().map(|(&a, &c)| (a, c)), n)
+    }
+
+    /// Returns the `n` most written memory addresses, and how many times
+    /// each was written, most written first.
+    pub fn most_written(&self, n: usize) -> Vec<(usize, u64)> {
+        top(self.writes.iter().map(|(&a, &c)| (a, c)), n)
+    }
+}
+
+// Returns the `n` highest counts, breaking ties by address.
+fn top<I>(counts: I, n: usize) -> Vec<(usize, u64)>
+where
+    I: Iterator<Item = (usize, u64)>,
+{
+    let mut v: Vec<(usize, u64)> = counts.collect();
+    v.sort_by_key(|&(address, count)| (std::cmp::Reverse(count), address));
+    v.truncate(n);
+    v
+}
+
+impl<W: Word> Tracer<W> for Profile<W> {
+    fn trace(&mut self, event: &Event<W>) {
+        self.total += 1;
+        if let Some(inst) = event.instruction_kind() {
+            self.executed
+                .entry(event.ip)
+                .or_insert_with(|| (0, inst.clone()))
+                .0 += 1;
+            if inst.is_jump() && event.next <= event.ip {
+                *self.loops.entry((event.next, event.ip)).or_insert(0) += 1;
+            }
+        }
+        for operand in event.operands() {
+            if let Operand::Memory { address, .. } = operand {
+                *self.reads.entry(*address).or_insert(0) += 1;
+            }
+        }
+        if let Some(w) = &event.write {
+            *self.writes.entry(w.address).or_insert(0) += 1;
+        }
+    }
+}
+
+impl<W: Word> fmt::Display for Profile<W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} instructions executed", self.total)?;
+
+        writeln!(f, "\nHottest instructions:")?;
+        for (address, count) in self.hottest(10) {
+            let line = Line {
+                address,
+                kind: Kind::Instruction(self.executed[&address].1.clone()),
+            };
+            writeln!(f, "{:>12}  {}", count, line)?;
+        }
+
+        writeln!(f, "\nHottest loops:")?;
+        for l in self.hot_loops(10) {
+            writeln!(f, "{:>12}  {:04}..{:04}", l.iterations, l.start, l.end)?;
+        }
+
+        writeln!(f, "\nMost read addresses:")?;
+        for (address, count) in self.most_read(10) {
+            writeln!(f, "{:>12}  [{}]", count, address)?;
+        }
+
+        writeln!(f, "\nMost written addresses:")?;
+        for (address, count) in self.most_written(10) {
+            writeln!(f, "{:>12}  [{}]", count, address)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+use super::assemble;
+
+// Sums the numbers from the input down to 1.
+#[cfg(test)]
+const SUM: &str = "
+            in -> [n]
+    loop:   add [acc], [n] -> [acc]
+            add [n], #-1 -> [n]
+            jt [n], #loop
+            out [acc]
+            hlt
+    n:      data 0
+    acc:    data 0
+";
+
+#[test]
+fn test_trace_writer() {
+    let trace = Arc::new(Mutex::new(TraceWriter::new(vec![])));
+    let mut program = Program::from(assemble(SUM).unwrap());
+    program.set_tracer(trace.clone());
+    assert_eq!(program.input(2).execute().unwrap(), vec![3]);
+
+    // Clones do not take the tracer with them.
+    program
+        .clone()
+        .reset_to_initial()
+        .input(5)
+        .execute()
+        .unwrap();
+
+    drop(program);
+    let trace = Arc::try_unwrap(trace).ok().unwrap().into_inner().unwrap();
+    let trace = String::from_utf8(trace.finish().unwrap()).unwrap();
+    let lines: Vec<&str> = trace.lines().collect();
+    assert_eq!(
+        lines,
+        vec![
+            "0000: IN -> [16] ; rb=0 ; - ; [16]=0->2",
+            "0002: ADD [17], [16] -> [17] ; rb=0 ; [17]=0 [16]=2 ; [17]=0->2",
+            "0006: ADD [16], #-1 -> [16] ; rb=0 ; [16]=2 #-1 ; [16]=2->1",
+            "0010: JT [16], #2 ; rb=0 ; [16]=1 #2 ; -",
+            "0002: ADD [17], [16] -> [17] ; rb=0 ; [17]=2 [16]=1 ; [17]=2->3",
+            "0006: ADD [16], #-1 -> [16] ; rb=0 ; [16]=1 #-1 ; [16]=1->0",
+            "0010: JT [16], #2 ; rb=0 ; [16]=0 #2 ; -",
+            "0013: OUT [17] ; rb=0 ; [17]=3 ; -",
+            "0015: HLT ; rb=0 ; - ; -",
+        ]
+    );
+}
+
+#[test]
+fn test_profile() {
+    let profile = Arc::new(Mutex::new(Profile::new()));
+    let mut program = Program::from(assemble(SUM).unwrap());
+    program.set_tracer(profile.clone());
+    assert_eq!(program.input(10).execute().unwrap(), vec![55]);
+    assert!(program.take_tracer().is_some());
+
+    let profile = profile.lock().unwrap();
+    assert_eq!(profile.instructions(), 1 + 3 * 10 + 2);
+    assert_eq!(profile.executions(2), 10);
+    assert_eq!(profile.executions(1), 0);
+    assert_eq!(profile.hottest(3), vec![(2, 10), (6, 10), (10, 10)]);
+    assert_eq!(
+        profile.hot_loops(10),
+        vec![Loop {
+            start: 2,
+            end: 10,
+            iterations: 9
+        }]
+    );
+    assert_eq!(profile.most_read(2), vec![(16, 30), (17, 11)]);
+    assert_eq!(profile.most_written(2), vec![(16, 11), (17, 10)]);
+
+    let summary = profile.to_string();
+    assert!(summary.starts_with("33 instructions executed\n"));
+    assert!(summary.contains("          10  0002: ADD [17], [16] -> [17]\n"));
+    assert!(summary.contains("           9  0002..0010\n"));
+}
@@ -0,0 +1,138 @@
+//! Cell types for intcode memory.
+//!
+//! A `Program` is generic over the type of each cell of its memory, which is
+//! also the type of its input and output values.
+//! `Word` is implemented for `i64`, the default, `i128`, and `num::BigInt`,
+//! for programs whose values do not fit in 64 bits.
+//!
+//! Fixed-width words wrap on overflow, unless the program was told to use
+//! checked arithmetic with `Program::set_checked_arithmetic`, in which case
+//! overflow stops the program with `IntcodeError::Overflow`.
+//! A `BigInt` never overflows.
+use num::{BigInt, Signed, ToPrimitive, Zero};
+use std::fmt;
+use std::hash::Hash;
+use std::str::FromStr;
+
+/// Word is the type of a single cell of intcode memory.
+pub trait Word:
+    Clone + fmt::Debug + fmt::Display + FromStr + Hash + Ord + Send + Sync + 'static
+{
+    /// Converts `n` to a word.
+    fn from_i64(n: i64) -> Self;
+
+    /// Converts the word to an `i64`, if it fits.
+    fn to_i64(&self) -> Option<i64>;
+
+    /// Converts the word to a `usize`, if it fits.
+    fn to_usize(&self) -> Option<usize>;
+
+    fn is_zero(&self) -> bool;
+    fn is_negative(&self) -> bool;
+
+    /// Returns `self + other`, or `None` on overflow.
+    fn checked_add(&self, other: &Self) -> Option<Self>;
+
+    /// Returns `self * other`, or `None` on overflow.
+    fn checked_mul(&self, other: &Self) -> Option<Self>;
+
+    /// Returns `self + other`, wrapping around on overflow.
+    fn wrapping_add(&self, other: &Self) -> Self;
+
+    /// Returns `self * other`, wrapping around on overflow.
+    fn wrapping_mul(&self, other: &Self) -> Self;
+
+    /// Converts the word to an `i64`, saturating at `i64::MIN` or `i64::MAX`
+    /// if it does not fit.
+    fn saturating_i64(&self) -> i64 {
+        match self.to_i64() {
+            Some(n) => n,
+            None if self.is_negative() => i64::MIN,
+            None => i64::MAX,
+        }
+    }
+}
+
+macro_rules! impl_word {
+    ($($t:ty),*) => {
+        $(
+            impl Word for $t {
+                fn from_i64(n: i64) -> Self {
+                    <$t>::from(n)
+                }
+
+                fn to_i64(&self) -> Option<i64> {
+                    ToPrimitive::to_i64(self)
+                }
+
+                fn to_usize(&self) -> Option<usize> {
+                    ToPrimitive::to_usize(self)
+                }
+
+                fn is_zero(&self) -> bool {
+                    *self == 0
+                }
+
+                fn is_negative(&self) -> bool {
+                    *self < 0
+                }
+
+                fn checked_add(&self, other: &Self) -> Option<Self> {
+                    <$t>::checked_add(*self, *other)
+                }
+
+                fn checked_mul(&self, other: &Self) -> Option<Self> {
+                    <$t>::checked_mul(*self, *other)
+                }
+
+                fn wrapping_add(&self, other: &Self) -> Self {
+                    <$t>::wrapping_add(*self, *other)
+                }
+
+                fn wrapping_mul(&self, other: &Self) -> Self {
+                    <$t>::wrapping_mul(*self, *other)
+                }
+            }
+        )*
+    };
+}
+
+impl_word!(i64, i128);
+
+impl Word for BigInt {
+    fn from_i64(n: i64) -> Self {
+        BigInt::from(n)
+    }
+
+    fn to_i64(&self) -> Option<i64> {
+        ToPrimitive::to_i64(self)
+    }
+
+    fn to_usize(&self) -> Option<usize> {
+        ToPrimitive::to_usize(self)
+    }
+
+    fn is_zero(&self) -> bool {
+        Zero::is_zero(self)
+    }
+
+    fn is_negative(&self) -> bool {
+        Signed::is_negative(self)
+    }
+
+    fn checked_add(&self, other: &Self) -> Option<Self> {
+        Some(self + other)
+    }
+
+    fn checked_mul(&self, other: &Self) -> Option<Self> {
+        Some(self * other)
+    }
+
+    fn wrapping_add(&self, other: &Self) -> Self {
+        self + other
+    }
+
+    fn wrapping_mul(&self, other: &Self) -> Self {
+        self * other
+    }
+}
@@ -59,21 +59,10 @@ use aoc2020::Input;
 use std::io::{Error, ErrorKind, Result};
 
 fn main() -> Result<()> {
-    let infile: String = match std::env::args().nth(1) {
-        Some(v) => v,
-        None => {
-            return Err(Error::new(
-                ErrorKind::InvalidInput,
-                "no input file specified",
-            ));
-        }
-    };
-    let input = Input::from_file(infile)?;
+    let input = Input::from_args()?;
 
     let nums: Vec<i32> = input
-        .lines()
-        .unwrap()
-        .iter()
+        .lines_iter()
         .map(|s| s.parse::<i32>().unwrap())
         .collect();
 
@@ -111,9 +100,7 @@ fn test_find_two() {
     let raw_input = "1721\n979\n366\n299\n675\n1456";
     let input = Input::from(raw_input);
     let nums: Vec<i32> = input
-        .lines()
-        .unwrap()
-        .iter()
+        .lines_iter()
         .map(|s| s.parse::<i32>().unwrap())
         .collect();
 
@@ -144,9 +131,7 @@ fn test_find_three() {
     let raw_input = "1721\n979\n366\n299\n675\n1456";
     let input = Input::from(raw_input);
     let nums: Vec<i32> = input
-        .lines()
-        .unwrap()
-        .iter()
+        .lines_iter()
         .map(|s| s.parse::<i32>().unwrap())
         .collect();
 
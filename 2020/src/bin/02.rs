@@ -45,20 +45,11 @@ use std::io::{Error, ErrorKind, Result};
 use std::str::FromStr;
 
 fn main() -> Result<()> {
-    let infile: String = match std::env::args().nth(1) {
-        Some(v) => v,
-        None => {
-            return Err(Error::new(
-                ErrorKind::InvalidInput,
-                "no input file specified",
-            ));
-        }
-    };
-    let input = Input::from_file(infile)?;
+    let input = Input::from_args()?;
 
     // Part 1: How many passwords are valid?
     let mut num_valid = 0;
-    for line in input.lines().unwrap() {
+    for line in input.lines() {
         let entry = match Entry::from_str(&line) {
             Ok(e) => e,
             Err(err) => {
@@ -74,7 +65,7 @@ fn main() -> Result<()> {
     // Part 2:
     // Use the valid2 method, instead.
     let mut n = 0;
-    for line in input.lines().unwrap() {
+    for line in input.lines() {
         let e = match Entry::from_str(&line) {
             Ok(e) => e,
             Err(err) => {
@@ -133,7 +124,7 @@ fn test_entry_valid() {
     let input = Input::from(raw_input);
 
     let mut n: usize = 0;
-    for line in input.lines().unwrap() {
+    for line in input.lines() {
         let entry = Entry::from_str(&line).unwrap();
         if entry.valid() {
             n += 1;
@@ -149,7 +140,7 @@ fn test_entry_valid2() {
     let input = Input::from(raw_input);
 
     let mut n: usize = 0;
-    for line in input.lines().unwrap() {
+    for line in input.lines() {
         let entry = Entry::from_str(&line).unwrap();
         if entry.valid2() {
             println!("{:?}: valid", line);
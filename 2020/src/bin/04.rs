@@ -1,19 +1,10 @@
 //! Day 4: Passport processing.
 
 use aoc2020::Input;
-use std::io::{Error, ErrorKind, Result};
+use std::io::Result;
 
 fn main() -> Result<()> {
-    let infile: String = match std::env::args().nth(1) {
-        Some(v) => v,
-        None => {
-            return Err(Error::new(
-                ErrorKind::InvalidInput,
-                "no input file specified",
-            ));
-        }
-    };
-    let input = Input::from_file(infile)?;
+    let input = Input::from_args()?;
     let lines = split_passports(&input);
 
     let mut passports: Vec<Vec<(String, String)>> = vec![];
@@ -45,20 +36,7 @@ fn main() -> Result<()> {
 // Coerce the input to be a vector of strings, where each string is guaranteed
 // to be a full passport record.
 fn split_passports(input: &Input) -> Vec<String> {
-    let mut passports = vec![];
-    let mut buf = vec![];
-    for line in input.raw_lines().unwrap() {
-        if line.len() == 0 {
-            let s = buf.join(" ").to_owned();
-            passports.push(s);
-            buf.clear();
-        } else {
-            buf.push(line);
-        }
-    }
-    let s = buf.join(" ").to_owned();
-    passports.push(s);
-    passports
+    input.paragraphs().collect()
 }
 
 // Given a single-line string containing all of the fields in a passport,
@@ -1,20 +1,11 @@
 //! Day 5: Binary Boarding
 
 use aoc2020::Input;
-use std::io::{Error, ErrorKind, Result};
+use std::io::Result;
 
 fn main() -> Result<()> {
-    let infile: String = match std::env::args().nth(1) {
-        Some(v) => v,
-        None => {
-            return Err(Error::new(
-                ErrorKind::InvalidInput,
-                "no input file specified",
-            ));
-        }
-    };
-    let input = Input::from_file(infile)?;
-    let boarding_passes: Vec<String> = input.lines().unwrap();
+    let input = Input::from_args()?;
+    let boarding_passes: Vec<String> = input.lines();
     let mut seat_ids: Vec<usize> = boarding_passes.iter().map(|s| seat_id(&s)).collect();
     seat_ids.sort_unstable();
 
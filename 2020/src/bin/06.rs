@@ -1,22 +1,10 @@
 //! Day 6: Custom Customs
 
-use aoc2020::Input;
-use std::{
-    collections::HashMap,
-    io::{Error, ErrorKind, Result},
-};
+use aoc2020::{Input, Section};
+use std::{collections::HashMap, io::Result};
 
 fn main() -> Result<()> {
-    let infile: String = match std::env::args().nth(1) {
-        Some(v) => v,
-        None => {
-            return Err(Error::new(
-                ErrorKind::InvalidInput,
-                "no input file specified",
-            ));
-        }
-    };
-    let input = Input::from_file(infile)?;
+    let input = Input::from_args()?;
     let answers = collect_group_answers(&input);
 
     // Part 1: Sum the number of answers from all groups.
@@ -30,20 +18,7 @@ fn main() -> Result<()> {
 }
 
 fn collect_group_answers(input: &Input) -> Vec<GroupAnswers> {
-    let mut v = vec![];
-    let mut buf = vec![];
-    for line in input.raw_lines().unwrap() {
-        if line.len() == 0 {
-            let answers = GroupAnswers::from(buf.clone());
-            v.push(answers);
-            buf.clear();
-        } else {
-            buf.push(line);
-        }
-    }
-    let answers = GroupAnswers::from(buf);
-    v.push(answers);
-    v
+    input.sections().map(GroupAnswers::from).collect()
 }
 
 #[derive(Clone)]
@@ -52,11 +27,12 @@ struct GroupAnswers {
     answers: HashMap<char, usize>,
 }
 
-impl From<Vec<String>> for GroupAnswers {
-    fn from(v: Vec<String>) -> Self {
-        let group_size = v.len();
+impl From<Section<'_>> for GroupAnswers {
+    fn from(section: Section<'_>) -> Self {
+        let mut group_size = 0;
         let mut answers = HashMap::new();
-        for s in v {
+        for s in section.lines() {
+            group_size += 1;
             for c in s.chars() {
                 if answers.contains_key(&c) {
                     if let Some(n) = answers.get_mut(&c) {
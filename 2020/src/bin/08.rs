@@ -167,16 +167,7 @@ use std::{
 };
 
 fn main() -> Result<()> {
-    let infile: String = match std::env::args().nth(1) {
-        Some(v) => v,
-        None => {
-            return Err(Error::new(
-                ErrorKind::InvalidInput,
-                "no input file specified",
-            ));
-        }
-    };
-    let input = Input::from_file(infile)?;
+    let input = Input::from_args()?;
 
     let instructions = parse_instructions(&input)?;
 
@@ -357,7 +348,7 @@ impl Program {
 /// Returns a `Vec` of `Instruction`s parsed from the puzzle input.
 fn parse_instructions(input: &Input) -> Result<Vec<Instruction>> {
     let mut instructions = vec![];
-    for line in input.lines().unwrap() {
+    for line in input.lines() {
         match Instruction::from_str(&line) {
             Ok(i) => {
                 instructions.push(i);
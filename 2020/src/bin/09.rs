@@ -1,23 +1,12 @@
 //! Day 9: Encoding Error
 
 use aoc2020::Input;
-use std::io::{Error, ErrorKind, Result};
+use std::io::Result;
 
 fn main() -> Result<()> {
-    let infile: String = match std::env::args().nth(1) {
-        Some(v) => v,
-        None => {
-            return Err(Error::new(
-                ErrorKind::InvalidInput,
-                "no input file specified",
-            ));
-        }
-    };
-    let input = Input::from_file(infile)?;
+    let input = Input::from_args()?;
     let numbers: Vec<isize> = input
-        .lines()
-        .unwrap()
-        .iter()
+        .lines_iter()
         .map(|s| match s.parse::<isize>() {
             Ok(n) => n,
             Err(e) => {
@@ -116,9 +105,7 @@ fn test_break_xmas_encryption() {
 576";
     let input = Input::from(input);
     let numbers: Vec<isize> = input
-        .lines()
-        .unwrap()
-        .iter()
+        .lines_iter()
         .map(|s| match s.parse::<isize>() {
             Ok(n) => n,
             Err(e) => {
@@ -1,23 +1,11 @@
 //! Day 10: Adapter Array
 
 use aoc2020::Input;
-use std::{
-    convert::TryInto,
-    io::{Error, ErrorKind, Result},
-};
+use std::{convert::TryInto, io::Result};
 
 fn main() -> Result<()> {
-    let infile: String = match std::env::args().nth(1) {
-        Some(v) => v,
-        None => {
-            return Err(Error::new(
-                ErrorKind::InvalidInput,
-                "no input file specified",
-            ));
-        }
-    };
-    let input = Input::from_file(infile)?;
-    let adapters = input.numbers().unwrap();
+    let input = Input::from_args()?;
+    let adapters = input.numbers()?;
 
     // Part 1: Using all of the adapters,
     // multiply the number of adapters with a joltage difference of 1,
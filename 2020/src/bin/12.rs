@@ -1,23 +1,10 @@
 //! Day 12: Rain Risk
 
 use aoc2020::Input;
-use std::{
-    io::{Error, ErrorKind, Result},
-    num::ParseIntError,
-    str::FromStr,
-};
+use std::{io::Result, num::ParseIntError, str::FromStr};
 
 fn main() -> Result<()> {
-    let infile: String = match std::env::args().nth(1) {
-        Some(v) => v,
-        None => {
-            return Err(Error::new(
-                ErrorKind::InvalidInput,
-                "no input file specified",
-            ));
-        }
-    };
-    let input = Input::from_file(infile)?;
+    let input = Input::from_args()?;
     let instructions = parse_instructions(input)?;
 
     // Part 1:
@@ -188,7 +175,7 @@ impl Ferry {
 
 fn parse_instructions(input: Input) -> Result<Vec<Instruction>> {
     let mut v: Vec<Instruction> = vec![];
-    for line in input.lines().unwrap() {
+    for line in input.lines() {
         let inst = Instruction::from_str(&line).unwrap();
         v.push(inst);
     }
@@ -1,20 +1,11 @@
 //! Day 13: Shuttle Search
 
 use aoc2020::Input;
-use std::io::{Error, ErrorKind, Result};
+use std::io::Result;
 
 fn main() -> Result<()> {
-    let infile: String = match std::env::args().nth(1) {
-        Some(v) => v,
-        None => {
-            return Err(Error::new(
-                ErrorKind::InvalidInput,
-                "no input file specified",
-            ));
-        }
-    };
-    let input = Input::from_file(infile)?;
-    let lines = input.lines().unwrap();
+    let input = Input::from_args()?;
+    let lines = input.lines();
     let departure_time: usize = lines.iter().nth(0).unwrap().parse().unwrap();
     let schedule: Vec<String> = lines
         .iter()
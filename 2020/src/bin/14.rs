@@ -1,25 +1,11 @@
 //! Day 14: Docking Data
 
 use aoc2020::Input;
-use std::io::{Error, ErrorKind, Result};
+use std::io::Result;
 
 fn main() -> Result<()> {
-    let infile: String = match std::env::args().nth(1) {
-        Some(v) => v,
-        None => {
-            return Err(Error::new(
-                ErrorKind::InvalidInput,
-                "no input file specified",
-            ));
-        }
-    };
-    let input = Input::from_file(infile)?;
-    let instructions: Vec<Instruction> = input
-        .lines()
-        .unwrap()
-        .iter()
-        .map(|ln| Instruction::from_str(ln).unwrap())
-        .collect();
+    let input = Input::from_args()?;
+    let instructions: Vec<Instruction> = input.parse_lines()?;
 
     let mut program = Program::default();
     program.run(&instructions);
@@ -1,26 +1,11 @@
 //! Day 15: Rambunctious Recitation
 
 use aoc2020::Input;
-use std::io::{Error, ErrorKind, Result};
+use std::io::Result;
 
 fn main() -> Result<()> {
-    let infile = match std::env::args().nth(1) {
-        Some(v) => v,
-        None => {
-            return Err(Error::new(
-                ErrorKind::InvalidInput,
-                "no input file specified",
-            ));
-        }
-    };
-    let input = Input::from_file(infile)?;
-    let nums: Vec<usize> = input
-        .lines()
-        .unwrap()
-        .iter()
-        .map(|ln| ln.split(",").map(|s| s.parse::<usize>().unwrap()))
-        .flatten()
-        .collect();
+    let input = Input::from_args()?;
+    let nums: Vec<usize> = input.parse_csv()?;
 
     println!("Part 1: {}", findn(2020, &nums));
 
@@ -2,23 +2,14 @@
 
 use aoc2020::Input;
 use std::{
-    io::{Error, ErrorKind, Result},
+    io::Result,
     num::ParseIntError,
     ops::RangeInclusive,
     str::FromStr,
 };
 
 fn main() -> Result<()> {
-    let infile = match std::env::args().nth(1) {
-        Some(v) => v,
-        None => {
-            return Err(Error::new(
-                ErrorKind::InvalidInput,
-                "no input file specified",
-            ));
-        }
-    };
-    let input = Input::from_file(infile)?;
+    let input = Input::from_args()?;
     let input = parse_input(&input)?;
 
     // Part 1:
@@ -40,7 +31,7 @@ fn parse_input(input: &Input) -> Result<PuzzleInput> {
     let mut rules = vec![];
     let mut skip: usize = 0;
     let mut your_ticket_idx: usize = 0;
-    let lines = input.lines().unwrap();
+    let lines = input.lines();
     for (i, line) in lines.iter().enumerate() {
         if line.starts_with("your ticket:") {
             skip += 1;
@@ -1,24 +1,13 @@
 //! Day 1: Sonar Sweep
 
 use aoc2021::Input;
-use std::io::{Error, ErrorKind, Result};
+use std::io::Result;
 
 fn main() -> Result<()> {
-    let infile: String = match std::env::args().nth(1) {
-        Some(v) => v,
-        None => {
-            return Err(Error::new(
-                ErrorKind::InvalidInput,
-                "no input file specified",
-            ));
-        }
-    };
-    let input = Input::from_file(infile)?;
+    let input = Input::from_args()?;
 
     let nums: Vec<u16> = input
-        .lines()
-        .unwrap()
-        .iter()
+        .lines_iter()
         .map(|s| s.parse::<u16>().unwrap())
         .collect();
 
@@ -67,9 +56,7 @@ fn test_find_three() {
     let raw_input = "1721\n979\n366\n299\n675\n1456";
     let input = Input::from(raw_input);
     let nums: Vec<i32> = input
-        .lines()
-        .unwrap()
-        .iter()
+        .lines_iter()
         .map(|s| s.parse::<i32>().unwrap())
         .collect();
 
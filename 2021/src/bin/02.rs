@@ -1,26 +1,12 @@
 //! Day 2: Dive!
 
 use aoc2021::Input;
-use std::io::{Error, ErrorKind, Result};
+use std::io::Result;
 
 fn main() -> Result<()> {
-    let infile: String = match std::env::args().nth(1) {
-        Some(v) => v,
-        None => {
-            return Err(Error::new(
-                ErrorKind::InvalidInput,
-                "no input file specified",
-            ));
-        }
-    };
-    let input = Input::from_file(infile)?;
+    let input = Input::from_args()?;
 
-    let directions: Vec<Direction> = input
-        .lines()
-        .unwrap()
-        .iter()
-        .map(|s| Direction::from(s.as_str()))
-        .collect();
+    let directions: Vec<Direction> = input.lines_iter().map(Direction::from).collect();
 
     // Part 1: Calculate the horizontal position and depth after following the
     // planned course.
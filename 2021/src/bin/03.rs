@@ -2,24 +2,13 @@
 
 use aoc2021::Input;
 use itertools::partition;
-use std::io::{Error, ErrorKind, Result};
+use std::io::Result;
 
 fn main() -> Result<()> {
-    let infile: String = match std::env::args().nth(1) {
-        Some(v) => v,
-        None => {
-            return Err(Error::new(
-                ErrorKind::InvalidInput,
-                "no input file specified",
-            ));
-        }
-    };
-    let input = Input::from_file(infile)?;
+    let input = Input::from_args()?;
 
     let values: Vec<usize> = input
-        .lines()
-        .unwrap()
-        .iter()
+        .lines_iter()
         .map(|s| usize::from_str_radix(s, 2).unwrap())
         .collect();
 
@@ -4,62 +4,27 @@ use aoc2021::Input;
 use std::io::{Error, ErrorKind, Result};
 
 fn main() -> Result<()> {
-    let infile: String = match std::env::args().nth(1) {
-        Some(v) => v,
-        None => {
-            return Err(Error::new(
-                ErrorKind::InvalidInput,
-                "no input file specified",
-            ));
-        }
-    };
-    let input = Input::from_file(infile)?;
-
-    // Parse the numbers that get called.
-    let nums: Vec<usize> = input
-        .lines()
-        .unwrap()
-        .iter()
-        .nth(0)
-        .map(|s| {
-            s.split(",")
-                .map(|v| usize::from_str_radix(v, 10).unwrap())
-                .collect()
+    let input = Input::from_args()?;
+
+    // Parse the numbers that get called, from the first section, and the
+    // bingo boards from each of the sections after it.
+    let mut sections = input.sections();
+    let nums: Vec<usize> = sections
+        .next()
+        .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "empty input"))?
+        .as_str()
+        .split(',')
+        .map(|v| v.trim().parse().unwrap())
+        .collect();
+    let boards: Vec<Board> = sections
+        .map(|section| {
+            let rows: Vec<Vec<isize>> = section
+                .lines()
+                .map(|s| s.split_whitespace().map(|v| v.parse().unwrap()).collect())
+                .collect();
+            Board::from(rows)
         })
-        .unwrap();
-    //dbg!(nums);
-
-    // Parse the bingo cards.
-    let mut boards: Vec<Board> = vec![];
-    let lines: Vec<String> = input
-        .lines()
-        .unwrap()
-        .iter()
-        .skip(1)
-        .map(|s| s.to_string())
         .collect();
-    let mut skip: usize = 0;
-    loop {
-        let lns: Vec<Vec<isize>> = lines
-            .iter()
-            .skip(skip)
-            .take(5)
-            .map(|s| {
-                s.split_whitespace()
-                    .map(|v| isize::from_str_radix(v, 10).unwrap())
-                    .collect()
-            })
-            .collect();
-
-        if lns.len() < 5 {
-            break;
-        }
-
-        let b = Board::from(lns);
-        boards.push(b);
-        skip += 5;
-    }
-    //dbg!(&cards);
 
     // Part 1: Find the board that will win first.
     println!("{}", winning_board_score(&nums, &boards));
@@ -0,0 +1,69 @@
+//! Points on a two-dimensional, integer plane.
+
+use std::fmt;
+use std::ops::{Add, Sub};
+
+/// Point is a position on a plane, or an offset between two positions.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Point {
+    pub x: i64,
+    pub y: i64,
+}
+
+impl Point {
+    /// Returns a new point at (`x`, `y`).
+    pub fn new(x: i64, y: i64) -> Self {
+        Self { x, y }
+    }
+
+    /// Returns the Manhattan distance between this point, and `other`.
+    pub fn manhattan(&self, other: &Self) -> i64 {
+        (self.x - other.x).abs() + (self.y - other.y).abs()
+    }
+
+    /// Returns the four points directly above, below, left, and right of
+    /// this one.
+    pub fn neighbours(&self) -> [Self; 4] {
+        [
+            Self::new(self.x, self.y - 1),
+            Self::new(self.x + 1, self.y),
+            Self::new(self.x, self.y + 1),
+            Self::new(self.x - 1, self.y),
+        ]
+    }
+}
+
+impl fmt::Display for Point {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "({}, {})", self.x, self.y)
+    }
+}
+
+impl Add for Point {
+    type Output = Self;
+    fn add(self, other: Self) -> Self {
+        Self::new(self.x + other.x, self.y + other.y)
+    }
+}
+
+impl Sub for Point {
+    type Output = Self;
+    fn sub(self, other: Self) -> Self {
+        Self::new(self.x - other.x, self.y - other.y)
+    }
+}
+
+impl From<(i64, i64)> for Point {
+    fn from((x, y): (i64, i64)) -> Self {
+        Self::new(x, y)
+    }
+}
+
+#[test]
+fn test_manhattan() {
+    let p = Point::new(3, -4);
+    assert_eq!(p.manhattan(&Point::default()), 7);
+    assert_eq!((p - Point::new(1, 1)).manhattan(&p), 2);
+    assert_eq!(p + Point::from((1, 1)), Point::new(4, -3));
+    assert_eq!(p.to_string(), "(3, -4)");
+}
@@ -0,0 +1,105 @@
+//! Two-dimensional grids of cells, as most map-like puzzle inputs are.
+
+use crate::{Input, Point};
+use std::fmt;
+
+/// Grid holds a rectangle of cells, stored row by row.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Grid<T> {
+    cells: Vec<T>,
+    width: usize,
+    height: usize,
+}
+
+impl<T> Grid<T> {
+    /// Parses a grid from the lines of `input`, turning each character into
+    /// a cell with `f`.
+    ///
+    /// Returns `None` if the input is empty, or its lines are not all the same
+    /// length.
+    pub fn parse<F: FnMut(char) -> T>(input: &Input, mut f: F) -> Option<Self> {
+        let mut lines = input.lines_iter().peekable();
+        let width = lines.peek()?.chars().count();
+        let mut cells = vec![];
+        let mut height = 0;
+        for line in lines {
+            let n = cells.len();
+            cells.extend(line.chars().map(&mut f));
+            if cells.len() - n != width {
+                return None;
+            }
+            height += 1;
+        }
+        Some(Self {
+            cells,
+            width,
+            height,
+        })
+    }
+
+    /// Returns the number of columns in the grid.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Returns the number of rows in the grid.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Returns the cell at (`x`, `y`), or `None` if that is outside of the
+    /// grid.
+    pub fn get(&self, x: usize, y: usize) -> Option<&T> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        self.cells.get(y * self.width + x)
+    }
+
+    /// Returns the cell at `p`, or `None` if that is outside of the grid.
+    pub fn at(&self, p: Point) -> Option<&T> {
+        if p.x < 0 || p.y < 0 {
+            return None;
+        }
+        self.get(p.x as usize, p.y as usize)
+    }
+
+    /// Returns a mutable reference to the cell at (`x`, `y`).
+    pub fn get_mut(&mut self, x: usize, y: usize) -> Option<&mut T> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        self.cells.get_mut(y * self.width + x)
+    }
+
+    /// Returns an iterator over the rows of the grid.
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        self.cells.chunks(self.width)
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Grid<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in self.rows() {
+            for cell in row {
+                write!(f, "{}", cell)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_parse() {
+    let input = Input::from("#..\n.#.\n");
+    let grid = Grid::parse(&input, |c| c == '#').unwrap();
+    assert_eq!((grid.width(), grid.height()), (3, 2));
+    assert_eq!(grid.get(1, 1), Some(&true));
+    assert_eq!(grid.get(2, 0), Some(&false));
+    assert_eq!(grid.get(3, 0), None);
+    assert_eq!(grid.at(Point::new(0, -1)), None);
+
+    let ragged = Input::from("#..\n.#\n");
+    assert_eq!(Grid::parse(&ragged, |c| c), None);
+}
@@ -0,0 +1,410 @@
+use flate2::read::MultiGzDecoder;
+use std::error;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, prelude::*, Error, ErrorKind, Result};
+use std::path::Path;
+use std::str::FromStr;
+
+/// The first two bytes of every gzip file.
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Embeds the puzzle input in the file at `path` into the program at compile
+/// time, returning it as an `Input`.
+/// As with `include_str!`, `path` is relative to the file the macro is used
+/// in.
+///
+/// Like `Input::new`, an empty file is not accepted; since the file is
+/// embedded at compile time, this panics rather than returning an error.
+#[macro_export]
+macro_rules! include_input {
+    ($path:expr) => {
+        $crate::Input::new_from_str(include_str!($path))
+            .expect(concat!("empty puzzle input: ", $path))
+    };
+}
+
+/// Input can be used to hold, or load puzzle input.
+pub struct Input {
+    data: String,
+}
+
+impl Input {
+    /// Return a new Input holding the data in `s`.
+    ///
+    /// Refer to the other methods, such as `lines` for getting at the data.
+    pub fn new(s: String) -> Result<Self> {
+        if s.is_empty() {
+            return Err(Error::other("zero-length input"));
+        }
+        Ok(Self { data: s })
+    }
+
+    pub fn new_from_str(s: &str) -> Result<Self> {
+        Self::new(s.to_string())
+    }
+
+    /// Load the puzzle input from the file at the specified `path`.
+    ///
+    /// A `path` of `-` reads the puzzle input from standard input instead.
+    /// Gzip-compressed files are decompressed as they are read.
+    /// As with `new`, an empty file is an error, rather than an empty
+    /// puzzle input.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        if path == Path::new("-") {
+            return Self::from_stdin();
+        }
+        Self::from_reader(File::open(path)?)
+    }
+
+    /// Load the puzzle input from the file named by the first command-line
+    /// argument, or from standard input if there is no argument.
+    pub fn from_args() -> Result<Self> {
+        match std::env::args_os().nth(1) {
+            Some(path) => Self::from_file(path),
+            None => Self::from_stdin(),
+        }
+    }
+
+    /// Load the puzzle input from standard input.
+    pub fn from_stdin() -> Result<Self> {
+        Self::from_reader(io::stdin().lock())
+    }
+
+    /// Load the puzzle input from everything that can be read from `r`,
+    /// decompressing it first, if it is gzip-compressed.
+    ///
+    /// As with `new`, it is an error if there is nothing to read.
+    pub fn from_reader<R: Read>(mut r: R) -> Result<Self> {
+        let mut buffer = vec![];
+        r.read_to_end(&mut buffer)?;
+        if buffer.starts_with(&GZIP_MAGIC) {
+            let mut decompressed = vec![];
+            MultiGzDecoder::new(buffer.as_slice()).read_to_end(&mut decompressed)?;
+            buffer = decompressed;
+        }
+        let data = String::from_utf8(buffer).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+        Self::new(data)
+    }
+
+    /// Returns the input data, with any surrounding whitespace removed.
+    ///
+    /// `to_string` returns the same, as a `String`.
+    pub fn as_str(&self) -> &str {
+        self.data.trim()
+    }
+
+    /// Returns an iterator over each line of the puzzle input, without
+    /// removing any empty lines.
+    pub fn raw_lines_iter(&self) -> impl Iterator<Item = &str> {
+        self.data.split('\n')
+    }
+
+    /// Returns an iterator over the non-empty lines of the puzzle input, with
+    /// their line endings removed.
+    ///
+    /// Unlike `lines`, the lines are borrowed from the input, rather than
+    /// copied.
+    pub fn lines_iter(&self) -> impl Iterator<Item = &str> {
+        self.data.lines().filter(|s| !s.is_empty())
+    }
+
+    /// Returns each line of the puzzle input, without removing any empty
+    /// lines.
+    pub fn raw_lines(&self) -> Vec<String> {
+        self.raw_lines_iter().map(String::from).collect()
+    }
+
+    /// Returns the puzzle input, split into lines with the newline characters
+    /// removed.
+    /// You may need to do additional parsing on the elements in the returned
+    /// `Vec<String>` to make the puzzle input useful.
+    pub fn lines(&self) -> Vec<String> {
+        self.lines_iter().map(String::from).collect()
+    }
+
+    /// Returns the puzzle input split on the given pattern `pat`, after it has
+    /// been split into lines.
+    pub fn split_lines(&self, pat: &str) -> Vec<Vec<String>> {
+        self.lines_iter()
+            .map(|line| line.split(pat).map(String::from).collect())
+            .collect()
+    }
+
+    /// Returns an iterator over the sections of the puzzle input: the blocks
+    /// of lines separated by one or more blank lines.
+    ///
+    /// Lines holding nothing but whitespace count as blank, so input with
+    /// `\r\n` line endings is split the same way.
+    pub fn sections(&self) -> impl Iterator<Item = Section<'_>> {
+        Sections { rest: &self.data }
+    }
+
+    /// Returns each section of the puzzle input as a single line, with the
+    /// lines of the section joined by spaces.
+    /// This suits records that may be wrapped over several lines.
+    pub fn paragraphs(&self) -> impl Iterator<Item = String> + '_ {
+        self.sections()
+            .map(|section| section.lines().collect::<Vec<&str>>().join(" "))
+    }
+
+    /// Returns the input data as a `Vec<isize>`, assuming there is one
+    /// number per line.
+    pub fn numbers(&self) -> std::result::Result<Vec<isize>, InputError> {
+        self.parse_lines()
+    }
+
+    /// Parses each non-empty line of the puzzle input as a `T`.
+    ///
+    /// Returns an error for the first line that cannot be parsed.
+    pub fn parse_lines<T>(&self) -> std::result::Result<Vec<T>, InputError>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        self.data
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.is_empty())
+            .map(|(i, line)| {
+                line.parse()
+                    .map_err(|e| InputError::new(i + 1, None, line, e))
+            })
+            .collect()
+    }
+
+    /// Parses the puzzle input as comma-separated values, such as an intcode
+    /// program.
+    /// Whitespace around each value is ignored, and values may be spread
+    /// over several lines.
+    ///
+    /// Returns an error for the first value that cannot be parsed, including
+    /// the column it starts at.
+    pub fn parse_csv<T>(&self) -> std::result::Result<Vec<T>, InputError>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        let mut v = vec![];
+        for (i, line) in self.data.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let mut column = 1;
+            for field in line.split(',') {
+                let text = field.trim();
+                let start = column + field.chars().count() - field.trim_start().chars().count();
+                match text.parse() {
+                    Ok(x) => v.push(x),
+                    Err(e) => return Err(InputError::new(i + 1, Some(start), text, e)),
+                }
+                column += field.chars().count() + 1;
+            }
+        }
+        Ok(v)
+    }
+}
+
+/// InputError is returned when part of the puzzle input cannot be parsed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InputError {
+    /// The 1-based line the error is on.
+    pub line: usize,
+
+    /// The 1-based column the offending text starts at, counted in
+    /// characters rather than bytes, if it is known.
+    pub column: Option<usize>,
+
+    /// The text that could not be parsed.
+    pub text: String,
+
+    /// Why the text could not be parsed.
+    pub reason: String,
+}
+
+impl InputError {
+    fn new<E: fmt::Display>(line: usize, column: Option<usize>, text: &str, e: E) -> Self {
+        Self {
+            line,
+            column,
+            text: text.to_string(),
+            reason: e.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for InputError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}", self.line)?;
+        if let Some(column) = self.column {
+            write!(f, ", column {}", column)?;
+        }
+        write!(f, ": cannot parse {:?}: {}", self.text, self.reason)
+    }
+}
+
+impl error::Error for InputError {}
+
+impl From<InputError> for Error {
+    fn from(e: InputError) -> Self {
+        Error::new(ErrorKind::InvalidData, e)
+    }
+}
+
+impl fmt::Display for Input {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+// Unlike `Input::new`, the `From` implementations accept empty input, since
+// they cannot fail; they are meant for puzzle input written out in tests.
+impl From<&str> for Input {
+    fn from(s: &str) -> Self {
+        Self {
+            data: s.to_string(),
+        }
+    }
+}
+
+impl From<String> for Input {
+    fn from(s: String) -> Self {
+        Self { data: s }
+    }
+}
+
+/// Section is a block of lines from puzzle input, as returned by
+/// `Input::sections`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Section<'a> {
+    text: &'a str,
+}
+
+impl<'a> Section<'a> {
+    /// Returns an iterator over the lines of the section, without their line
+    /// endings.
+    pub fn lines(&self) -> impl Iterator<Item = &'a str> {
+        self.text.lines()
+    }
+
+    /// Returns the text of the section, without the line ending of its last
+    /// line.
+    pub fn as_str(&self) -> &'a str {
+        self.text
+    }
+}
+
+// Sections iterates over the sections of some text.
+struct Sections<'a> {
+    rest: &'a str,
+}
+
+impl<'a> Iterator for Sections<'a> {
+    type Item = Section<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut start = None;
+        let mut end = 0;
+        for line in self.rest.split_inclusive('\n') {
+            let blank = line.trim().is_empty();
+            if blank && start.is_some() {
+                break;
+            }
+            if !blank && start.is_none() {
+                start = Some(end);
+            }
+            end += line.len();
+        }
+
+        let text = &self.rest[start?..end];
+        self.rest = &self.rest[end..];
+        Some(Section {
+            text: text.trim_end_matches(&['\r', '\n'][..]),
+        })
+    }
+}
+
+#[test]
+fn test_from_reader() {
+    use flate2::{write::GzEncoder, Compression};
+
+    let input = Input::from_reader("1\n2\n".as_bytes()).unwrap();
+    assert_eq!(input.lines(), vec!["1", "2"]);
+
+    let mut gz = GzEncoder::new(vec![], Compression::default());
+    gz.write_all(b"1\n2\n").unwrap();
+    let input = Input::from_reader(gz.finish().unwrap().as_slice()).unwrap();
+    assert_eq!(input.lines(), vec!["1", "2"]);
+
+    let err = Input::from_reader(&[0xff, 0xfe][..]).err().unwrap();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+    let err = Input::from_reader(io::empty()).err().unwrap();
+    assert_eq!(err.to_string(), "zero-length input");
+
+    let input = include_input!("../Cargo.toml");
+    assert_eq!(input.lines_iter().next(), Some("[package]"));
+}
+
+#[test]
+fn test_lines() {
+    let input = Input::from("a b\r\n\nc\n");
+    assert_eq!(input.lines_iter().collect::<Vec<_>>(), vec!["a b", "c"]);
+    assert_eq!(input.lines(), vec!["a b", "c"]);
+    assert_eq!(input.raw_lines().len(), 4);
+    assert_eq!(input.split_lines(" "), vec![vec!["a", "b"], vec!["c"]]);
+    assert_eq!(input.as_str(), "a b\r\n\nc");
+    assert_eq!(input.to_string(), input.as_str());
+}
+
+#[test]
+fn test_sections() {
+    let input = Input::from("\na\nb\n\n\nc\r\n \r\nd\r\ne\r\n");
+    let sections: Vec<Vec<&str>> = input.sections().map(|s| s.lines().collect()).collect();
+    assert_eq!(sections, vec![vec!["a", "b"], vec!["c"], vec!["d", "e"]]);
+    assert_eq!(input.sections().nth(2).unwrap().as_str(), "d\r\ne");
+
+    let paragraphs: Vec<String> = input.paragraphs().collect();
+    assert_eq!(paragraphs, vec!["a b", "c", "d e"]);
+
+    assert_eq!(Input::from("\n\n").sections().count(), 0);
+}
+
+#[test]
+fn test_parse_lines() {
+    let input = Input::from("1\n-2\n\n3\n");
+    assert_eq!(input.parse_lines::<i32>(), Ok(vec![1, -2, 3]));
+
+    let input = Input::from("1\n\ntwo\n");
+    let err = input.parse_lines::<i32>().unwrap_err();
+    assert_eq!((err.line, err.column, err.text.as_str()), (3, None, "two"));
+    assert_eq!(
+        err.to_string(),
+        "line 3: cannot parse \"two\": invalid digit found in string"
+    );
+}
+
+#[test]
+fn test_parse_csv() {
+    let input = Input::from("1,0,0,3,99\n");
+    assert_eq!(input.parse_csv::<i64>(), Ok(vec![1, 0, 0, 3, 99]));
+
+    let input = Input::from("1, 2,\n3,4\n");
+    assert_eq!(input.parse_csv::<u8>().unwrap_err().column, Some(6));
+
+    let input = Input::from("1,2\n3, x4\n");
+    let err = input.parse_csv::<u8>().unwrap_err();
+    assert_eq!(
+        (err.line, err.column, err.text.as_str()),
+        (2, Some(4), "x4")
+    );
+
+    // Columns count characters, not bytes.
+    let input = Input::from("1,\u{a0}é,x\n");
+    let err = input.parse_csv::<u8>().unwrap_err();
+    assert_eq!((err.column, err.text.as_str()), (Some(4), "é"));
+    let input = Input::from("1,\u{a0}2,ü,x\n");
+    let err = input.parse_csv::<u8>().unwrap_err();
+    assert_eq!((err.column, err.text.as_str()), (Some(6), "ü"));
+}
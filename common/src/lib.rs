@@ -0,0 +1,31 @@
+//! Helper types and functions shared by the
+//! [Advent of Code](https://adventofcode.com) solutions for every year.
+
+pub mod geom;
+pub mod grid;
+mod input;
+pub mod parse;
+
+pub use geom::Point;
+pub use grid::Grid;
+pub use input::{Input, InputError, Section};
+
+/// The `util` module provides some convenience utilities that are not tied
+/// to a specific puzzle.
+pub mod util {
+    /// Return the larger of `a` or `b`.
+    pub fn max<T: PartialOrd>(a: T, b: T) -> T {
+        if a > b {
+            return a;
+        }
+        b
+    }
+
+    /// Return the smaller of `a` or `b`.
+    pub fn min<T: PartialOrd>(a: T, b: T) -> T {
+        if a < b {
+            return a;
+        }
+        b
+    }
+}
@@ -0,0 +1,34 @@
+//! Helpers for pulling values out of puzzle input.
+
+/// Returns every integer in `s`, in order, ignoring whatever is between
+/// them.
+/// A `-` immediately before a number makes it negative.
+pub fn integers(s: &str) -> Vec<i64> {
+    let mut v = vec![];
+    let mut chars = s.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        let negative = c == '-' && chars.peek().is_some_and(|(_, c)| c.is_ascii_digit());
+        if !negative && !c.is_ascii_digit() {
+            continue;
+        }
+        let mut end = i + 1;
+        while let Some(&(j, c)) = chars.peek() {
+            if !c.is_ascii_digit() {
+                break;
+            }
+            end = j + 1;
+            chars.next();
+        }
+        if let Ok(n) = s[i..end].parse() {
+            v.push(n);
+        }
+    }
+    v
+}
+
+#[test]
+fn test_integers() {
+    assert_eq!(integers("<x=-1, y=0, z=2>"), vec![-1, 0, 2]);
+    assert_eq!(integers("1-3 a: abcde"), vec![1, -3]);
+    assert_eq!(integers("no numbers - here"), Vec::<i64>::new());
+}